@@ -0,0 +1,21 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use advent_of_code::day_6::{find_marker_with, MarkerStrategy};
+
+fn bench_strategies(c: &mut Criterion) {
+    let input = include_str!("../src/test_files/day_6.txt");
+    let mut group = c.benchmark_group("find_marker");
+
+    for n in [4, 14] {
+        for strategy in [MarkerStrategy::HashSet, MarkerStrategy::RollingCount] {
+            group.bench_with_input(BenchmarkId::new(format!("{strategy:?}"), n), &n, |b, &n| {
+                b.iter(|| find_marker_with(input, n, strategy))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_strategies);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// A puzzle answer. Most are numbers, but some days spell theirs out in
+/// text, like the crate tops of day 5 or the CRT picture of day 10.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Int(i64),
+    Text(String),
+}
+
+impl Answer {
+    /// Whether `expected`, as typed by a user, is this answer. Numbers are
+    /// compared as numbers, so `+42` or ` 042` still match `42`; text
+    /// ignores trailing whitespace so a pasted multi-line answer matches.
+    pub fn matches(&self, expected: &str) -> bool {
+        match self {
+            Answer::Int(n) => expected.trim().parse::<i64>() == Ok(*n),
+            Answer::Text(s) => s.trim_end() == expected.trim_end(),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Int(n) => write!(f, "{n}"),
+            Answer::Text(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for Answer {
+            /// Falls back to text for the rare answer too big for an `i64`.
+            fn from(n: $t) -> Self {
+                i64::try_from(n).map_or_else(|_| Answer::Text(n.to_string()), Answer::Int)
+            }
+        })*
+    };
+}
+
+impl_from_int!(i32, i64, u32, u64, usize);
+
+#[test]
+fn test_answer() {
+    assert_eq!(Answer::Int(13140).to_string(), "13140");
+    assert!(Answer::Int(13140).matches("13140"));
+    assert!(Answer::Int(13140).matches(" +013140\n"));
+    assert!(!Answer::Int(13140).matches("13141"));
+    assert!(!Answer::Int(13140).matches("CMZ"));
+
+    assert_eq!(Answer::from(7_usize), Answer::Int(7));
+    assert_eq!(Answer::from(-3_i32), Answer::Int(-3));
+    assert_eq!(Answer::from(u64::MAX), Answer::Text(u64::MAX.to_string()));
+
+    let text = Answer::from("#.\n.#\n".to_string());
+    assert!(text.matches("#.\n.#"));
+    assert!(!text.matches(".#\n#."));
+    assert!(!Answer::Text("13140".into()).matches("+13140"));
+}
@@ -0,0 +1,259 @@
+use advent_of_code::{
+    answer::Answer,
+    cache::SolveCache,
+    day::Day,
+    manifest::{self, SortBy},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use std::{fs, io, panic, path::Path};
+
+const USAGE: &str =
+    "Usage: aoc DAY... [--day DAY] [--part 1|2] [--input PATH | --input-dir DIR] [--expect VALUE] [--no-cache]
+       aoc DAY [--part 1|2] --input PATH --watch
+       aoc manifest [--format text|json] [--sort day|time]
+       aoc descriptions";
+
+/// Compares an answer with the one the user expected, see
+/// `Answer::matches`.
+fn check_answer(day: Day, part: u8, answer: &Answer, expected: &str) -> Result<(), String> {
+    if answer.matches(expected) {
+        return Ok(());
+    }
+    Err(format!(
+        "Day {} part {part} does not match\n  expected: {}\n    actual: {}",
+        day.number(),
+        expected.trim_end(),
+        answer.to_string().trim_end()
+    ))
+}
+
+/// Every `*.txt` file in `dir` by name, in order, each with its contents
+/// or the error reading it.
+fn batch_inputs(dir: &Path) -> Result<Vec<(String, io::Result<String>)>> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "txt"));
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path))
+        })
+        .collect())
+}
+
+/// Solves every day and part for each file from `--input-dir`, reporting
+/// failures as it goes rather than stopping at the first.
+/// Solves one part, turning a panic in the solver into an error so one bad
+/// file doesn't end the batch.
+fn run_caught(day: Day, part: u8, input: &str) -> Result<Answer, String> {
+    match panic::catch_unwind(|| day.run(part, input)) {
+        Ok(answer) => answer.map_err(|e| e.to_string()),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("no message");
+            Err(format!("panicked: {message}"))
+        }
+    }
+}
+
+fn run_batch(
+    inputs: Vec<(String, io::Result<String>)>,
+    days: &[Day],
+    parts: &[u8],
+    cache: &mut SolveCache<Result<Answer, String>>,
+) -> Result<()> {
+    let mut failures = 0;
+    for (name, input) in &inputs {
+        let input = match input {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("{name}: {e}");
+                failures += 1;
+                continue;
+            }
+        };
+        for &day in days {
+            for &part in parts {
+                let label = if days.len() == 1 && parts.len() == 1 {
+                    name.clone()
+                } else {
+                    format!("{name} (day {} part {part})", day.number())
+                };
+                let answer = cache.get_or_solve(day.number(), part, input, |input| {
+                    run_caught(day, part, input)
+                });
+                match answer {
+                    Ok(answer) if answer.to_string().contains('\n') => {
+                        println!("{label}:\n{answer}")
+                    }
+                    Ok(answer) => println!("{label}: {answer}"),
+                    Err(e) => {
+                        eprintln!("{label}: {e}");
+                        failures += 1;
+                    }
+                }
+            }
+        }
+    }
+    if failures > 0 {
+        bail!("{failures} answers failed across {} inputs", inputs.len());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "watch")]
+fn run_watch(path: String, day: Day, parts: &[u8]) -> Result<()> {
+    advent_of_code::watch::watch(path.into(), day, parts)
+}
+
+#[cfg(not(feature = "watch"))]
+fn run_watch(_: String, _: Day, _: &[u8]) -> Result<()> {
+    bail!("--watch needs aoc built with --features watch")
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "descriptions") {
+        for day in Day::ALL {
+            let info = day.describe();
+            let [part_1, part_2] = info.answers;
+            println!(
+                "Day {:>2}  {} (part 1: {part_1}, part 2: {part_2})",
+                day.number(),
+                info.title
+            );
+        }
+        return Ok(());
+    }
+
+    let manifest = args.first().is_some_and(|arg| arg == "manifest");
+    let mut take_flag = |name: &str| -> Result<Option<String>> {
+        let Some(i) = args.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        if i + 1 >= args.len() {
+            bail!(USAGE);
+        }
+        let value = args.remove(i + 1);
+        args.remove(i);
+        Ok(Some(value))
+    };
+
+    if manifest {
+        let format = take_flag("--format")?;
+        let sort_by = take_flag("--sort")?.map_or(Ok(SortBy::default()), |s| s.parse())?;
+        let mut entries = manifest::build(&Day::ALL, Day::input);
+        manifest::sort(&mut entries, sort_by);
+        match format.as_deref() {
+            None | Some("text") => print!("{}", manifest::to_table(&entries)),
+            Some("json") => println!("{}", manifest::to_json(&entries)),
+            Some(other) => bail!("unknown format {other:?}, expected text or json"),
+        }
+        return Ok(());
+    }
+
+    let parts = match take_flag("--part")? {
+        Some(part) => vec![part.parse::<u8>()?],
+        None => vec![1, 2],
+    };
+    let day = take_flag("--day")?;
+    let expect = take_flag("--expect")?;
+    let input_path = take_flag("--input")?;
+    let input = input_path
+        .as_ref()
+        .map(|path| fs::read_to_string(path).with_context(|| format!("reading {path}")))
+        .transpose()?;
+    let input_dir = take_flag("--input-dir")?;
+    if input.is_some() && input_dir.is_some() {
+        bail!("--input and --input-dir can't be used together");
+    }
+
+    let watch = match args.iter().position(|arg| arg == "--watch") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    let mut cache = match args.iter().position(|arg| arg == "--no-cache") {
+        Some(i) => {
+            args.remove(i);
+            SolveCache::disabled()
+        }
+        None => SolveCache::new(),
+    };
+
+    args.extend(day);
+    if args.is_empty() {
+        bail!(USAGE);
+    }
+    let days = args
+        .iter()
+        .map(|arg| arg.parse::<Day>())
+        .collect::<Result<Vec<_>>>()?;
+    if expect.is_some() && (days.len() != 1 || parts.len() != 1) {
+        bail!("--expect needs exactly one day and a --part");
+    }
+
+    if watch {
+        let (Some(path), [day]) = (input_path, &days[..]) else {
+            bail!("--watch needs exactly one day and an --input file");
+        };
+        return run_watch(path, *day, &parts);
+    }
+
+    if let Some(dir) = input_dir {
+        if expect.is_some() {
+            bail!("--expect can't be used with --input-dir");
+        }
+        return run_batch(batch_inputs(Path::new(&dir))?, &days, &parts, &mut cache);
+    }
+
+    for day in days {
+        let input = input.as_deref().unwrap_or(day.input());
+        for &part in &parts {
+            let answer = cache.get_or_solve(day.number(), part, input, |input| {
+                day.run(part, input).map_err(|e| e.to_string())
+            });
+            let answer = answer.map_err(|e| anyhow!("{day} part {part}: {e}"))?;
+            if let Some(expected) = &expect {
+                if let Err(mismatch) = check_answer(day, part, &answer, expected) {
+                    eprintln!("{mismatch}");
+                    std::process::exit(1);
+                }
+            }
+            let answer = answer.to_string();
+            if answer.contains('\n') {
+                println!("Day {} part {part}:\n{answer}", day.number());
+            } else {
+                println!("Day {} part {part}: {answer}", day.number());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_check_answer() {
+    let sample = "mjqjpqmgbljsphdztnvjfqwrcgjmlb";
+    let answer = Day::D6.run(1, sample).unwrap();
+    assert_eq!(check_answer(Day::D6, 1, &answer, "7"), Ok(()));
+    assert_eq!(check_answer(Day::D6, 1, &answer, "+7 "), Ok(()));
+    let picture = Answer::Text("#.\n.#\n".to_string());
+    assert_eq!(check_answer(Day::D10, 2, &picture, "#.\n.#"), Ok(()));
+
+    let mismatch = check_answer(Day::D6, 1, &answer, "8").unwrap_err();
+    assert_eq!(
+        mismatch,
+        "Day 6 part 1 does not match\n  expected: 8\n    actual: 7"
+    );
+}
@@ -0,0 +1,4 @@
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> anyhow::Result<()> {
+    advent_of_code::day_12::main()
+}
@@ -0,0 +1,3 @@
+fn main() -> anyhow::Result<()> {
+    advent_of_code::day_13::main()
+}
@@ -0,0 +1,87 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Timeout;
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "solver ran out of time")
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Bounds how long a solver may run, either by a number of steps or a
+/// wall-clock deadline. What counts as a step is up to the solver; the
+/// default budget is unlimited.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Budget {
+    max_steps: Option<u64>,
+    deadline: Option<Instant>,
+    steps: u64,
+}
+
+impl Budget {
+    pub fn steps(max_steps: u64) -> Self {
+        Self {
+            max_steps: Some(max_steps),
+            ..Self::default()
+        }
+    }
+
+    pub fn timeout(duration: Duration) -> Self {
+        Self {
+            deadline: Some(Instant::now() + duration),
+            ..Self::default()
+        }
+    }
+
+    /// A deadline from `--timeout SECONDS` if it was passed, otherwise an
+    /// unlimited budget.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let Some(i) = args.iter().position(|arg| arg == "--timeout") else {
+            return Ok(Self::default());
+        };
+        let Some(secs) = args.get(i + 1) else {
+            bail!("Usage: --timeout SECONDS");
+        };
+        Ok(Self::timeout(Duration::try_from_secs_f64(secs.parse()?)?))
+    }
+
+    /// Records one step, failing once the step count or deadline is
+    /// exceeded.
+    pub fn tick(&mut self) -> Result<(), Timeout> {
+        self.steps += 1;
+        if self.max_steps.is_some_and(|max| self.steps > max)
+            || self.deadline.is_some_and(|d| Instant::now() > d)
+        {
+            return Err(Timeout);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_budget() {
+    let mut budget = Budget::steps(2);
+    assert_eq!(budget.tick(), Ok(()));
+    assert_eq!(budget.tick(), Ok(()));
+    assert_eq!(budget.tick(), Err(Timeout));
+
+    let mut budget = Budget::timeout(Duration::ZERO);
+    std::thread::sleep(Duration::from_millis(1));
+    assert_eq!(budget.tick(), Err(Timeout));
+
+    let mut budget = Budget::default();
+    assert!((0..1000).all(|_| budget.tick().is_ok()));
+
+    let args = ["--timeout", "1.5"].map(String::from);
+    assert!(Budget::from_args(&args).unwrap().deadline.is_some());
+    assert!(Budget::from_args(&args[..1]).is_err());
+    assert!(Budget::from_args(&[]).unwrap().deadline.is_none());
+}
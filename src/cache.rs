@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+/// Answers already computed in this process, keyed by day, part and a hash
+/// of the input, so solving an unchanged input again is free.
+pub struct SolveCache<T> {
+    answers: HashMap<(u8, u8, u64), T>,
+    enabled: bool,
+}
+
+impl<T: Clone> SolveCache<T> {
+    pub fn new() -> Self {
+        Self {
+            answers: HashMap::new(),
+            enabled: true,
+        }
+    }
+
+    /// A cache that never stores anything, for `--no-cache`.
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::new()
+        }
+    }
+
+    pub fn get_or_solve(
+        &mut self,
+        day: u8,
+        part: u8,
+        input: &str,
+        solve: impl FnOnce(&str) -> T,
+    ) -> T {
+        if !self.enabled {
+            return solve(input);
+        }
+
+        self.answers
+            .entry((day, part, fxhash::hash64(input)))
+            .or_insert_with(|| solve(input))
+            .clone()
+    }
+}
+
+impl<T: Clone> Default for SolveCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_solve_cache() {
+    let mut calls = 0;
+    let mut solve = |input: &str| {
+        calls += 1;
+        input.len()
+    };
+
+    let mut cache = SolveCache::new();
+    assert_eq!(cache.get_or_solve(6, 1, "abcd", &mut solve), 4);
+    assert_eq!(cache.get_or_solve(6, 1, "abcd", &mut solve), 4);
+    assert_eq!(cache.get_or_solve(6, 1, "abce", &mut solve), 4);
+    assert_eq!(cache.get_or_solve(6, 2, "abcd", &mut solve), 4);
+    assert_eq!(calls, 3);
+
+    let mut calls = 0;
+    let mut cache = SolveCache::disabled();
+    for _ in 0..2 {
+        cache.get_or_solve(6, 1, "abcd", |input| {
+            calls += 1;
+            input.len()
+        });
+    }
+    assert_eq!(calls, 2);
+}
@@ -0,0 +1,159 @@
+use std::ops::{Add, AddAssign, Sub};
+
+use serde::{Deserialize, Serialize};
+
+/// The integer types a `Coord` can be made of.
+pub trait Component: Copy + Ord + Add<Output = Self> + Sub<Output = Self> + AddAssign {
+    /// `|self - other|`, which unlike plain subtraction can't underflow for
+    /// `usize`. For signed types the result has to fit back in the type.
+    fn abs_diff(self, other: Self) -> Self;
+}
+
+macro_rules! impl_component {
+    ($($t:ty),*) => {
+        $(impl Component for $t {
+            fn abs_diff(self, other: Self) -> Self {
+                <$t>::abs_diff(self, other) as $t
+            }
+        })*
+    };
+}
+
+impl_component!(i32, i64, usize);
+
+/// A grid position. Subtracting follows the component type, so a `usize`
+/// coordinate panics in debug builds if it goes below zero; use
+/// `manhattan` for distances.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coord<T = i32> {
+    pub x: T,
+    pub y: T,
+}
+
+impl Coord {
+    pub fn signum(self) -> Self {
+        Self {
+            x: self.x.signum(),
+            y: self.y.signum(),
+        }
+    }
+}
+
+impl<T: Component> Coord<T> {
+    /// The taxicab distance to `other`, in the same type as the components.
+    pub fn manhattan(self, other: Self) -> T {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl<T: Component> Add for Coord<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl<T: Component> AddAssign for Coord<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: Component> Sub for Coord<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+/// Every cell on the segment from `a` to `b`, both ends included.
+///
+/// Uses Bresenham's algorithm, so any direction works; axis-aligned and
+/// 45-degree segments come out exact.
+pub fn rasterize_segment(a: Coord, b: Coord) -> impl Iterator<Item = Coord> {
+    let dx = (b.x - a.x).abs();
+    let dy = -(b.y - a.y).abs();
+    let step = (b - a).signum();
+    let mut err = dx + dy;
+    let mut next = Some(a);
+
+    std::iter::from_fn(move || {
+        let curr = next?;
+        next = (curr != b).then(|| {
+            let mut c = curr;
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                c.x += step.x;
+            }
+            if e2 <= dx {
+                err += dx;
+                c.y += step.y;
+            }
+            c
+        });
+        Some(curr)
+    })
+}
+
+#[cfg(test)]
+fn segment(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+    rasterize_segment(Coord { x: a.0, y: a.1 }, Coord { x: b.0, y: b.1 })
+        .map(|c| (c.x, c.y))
+        .collect()
+}
+
+#[test]
+fn test_rasterize_horizontal() {
+    assert_eq!(segment((0, 0), (3, 0)), [(0, 0), (1, 0), (2, 0), (3, 0)]);
+    assert_eq!(segment((2, 4), (0, 4)), [(2, 4), (1, 4), (0, 4)]);
+}
+
+#[test]
+fn test_rasterize_vertical() {
+    assert_eq!(segment((2, 5), (2, 2)), [(2, 5), (2, 4), (2, 3), (2, 2)]);
+    assert_eq!(segment((1, 1), (1, 1)), [(1, 1)]);
+}
+
+#[test]
+fn test_rasterize_diagonal() {
+    assert_eq!(segment((0, 0), (3, 3)), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+    assert_eq!(segment((3, 0), (0, 3)), [(3, 0), (2, 1), (1, 2), (0, 3)]);
+    assert_eq!(
+        segment((0, 0), (4, 2)),
+        [(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+    );
+}
+
+#[test]
+fn test_manhattan() {
+    let a = Coord { x: 3, y: -4 };
+    assert_eq!(a.manhattan(Coord { x: -1, y: 2 }), 10);
+    assert_eq!(a.manhattan(a), 0);
+
+    let (small, big) = (Coord { x: 2usize, y: 9 }, Coord { x: 7usize, y: 1 });
+    assert_eq!(small.manhattan(big), 13);
+    assert_eq!(big.manhattan(small), 13);
+
+    let far = i64::MAX / 4;
+    let a = Coord { x: far, y: -far };
+    let b = Coord { x: -far, y: far };
+    assert_eq!(a.manhattan(b), 4 * far);
+    assert_eq!(
+        a.manhattan(Coord {
+            x: far - 1,
+            y: 1 - far
+        }),
+        2
+    );
+    assert_eq!(a + b, Coord { x: 0, y: 0 });
+}
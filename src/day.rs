@@ -0,0 +1,308 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::answer::Answer;
+use crate::{
+    day_1, day_10, day_11, day_12, day_13, day_14, day_15, day_2, day_3, day_4, day_5, day_6,
+    day_7, day_8, day_9,
+};
+
+/// Whether a part's answer is a number or text, like day 5's crate tops.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnswerKind {
+    Int,
+    Text,
+}
+
+impl fmt::Display for AnswerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnswerKind::Int => write!(f, "integer"),
+            AnswerKind::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// What a day is about, for listing the binaries without opening them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DayInfo {
+    /// The puzzle name and a one-line summary of what it asks.
+    pub title: &'static str,
+    pub answers: [AnswerKind; 2],
+}
+
+/// Every puzzle day, so the CLI can go from a name on the command line to
+/// the solver for it in one place.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Day {
+    D1 = 1,
+    D2,
+    D3,
+    D4,
+    D5,
+    D6,
+    D7,
+    D8,
+    D9,
+    D10,
+    D11,
+    D12,
+    D13,
+    D14,
+    D15,
+}
+
+impl Day {
+    pub const ALL: [Day; 15] = [
+        Day::D1,
+        Day::D2,
+        Day::D3,
+        Day::D4,
+        Day::D5,
+        Day::D6,
+        Day::D7,
+        Day::D8,
+        Day::D9,
+        Day::D10,
+        Day::D11,
+        Day::D12,
+        Day::D13,
+        Day::D14,
+        Day::D15,
+    ];
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    /// The full puzzle input bundled with the day.
+    pub fn input(self) -> &'static str {
+        match self {
+            Day::D1 => include_str!("test_files/day_1.txt"),
+            Day::D2 => include_str!("test_files/day_2.txt"),
+            Day::D3 => include_str!("test_files/day_3.txt"),
+            Day::D4 => include_str!("test_files/day_4.txt"),
+            Day::D5 => include_str!("test_files/day_5.txt"),
+            Day::D6 => include_str!("test_files/day_6.txt"),
+            Day::D7 => include_str!("test_files/day_7.txt"),
+            Day::D8 => include_str!("test_files/day_8.txt"),
+            Day::D9 => include_str!("test_files/day_9.txt"),
+            Day::D10 => include_str!("test_files/day_10.txt"),
+            Day::D11 => include_str!("test_files/day_11.txt"),
+            Day::D12 => include_str!("test_files/day_12.txt"),
+            Day::D13 => include_str!("test_files/day_13.txt"),
+            Day::D14 => include_str!("test_files/day_14.txt"),
+            Day::D15 => include_str!("test_files/day_15.txt"),
+        }
+    }
+
+    /// The example from the puzzle text, small enough to check by hand.
+    pub fn sample(self) -> &'static str {
+        match self {
+            Day::D1 => include_str!("test_files/day_1_test.txt"),
+            Day::D2 => include_str!("test_files/day_2_test.txt"),
+            Day::D3 => include_str!("test_files/day_3_test.txt"),
+            Day::D4 => include_str!("test_files/day_4_test.txt"),
+            Day::D5 => include_str!("test_files/day_5_test.txt"),
+            Day::D6 => include_str!("test_files/day_6_test.txt"),
+            Day::D7 => include_str!("test_files/day_7_test.txt"),
+            Day::D8 => include_str!("test_files/day_8_test.txt"),
+            Day::D9 => include_str!("test_files/day_9_test.txt"),
+            Day::D10 => include_str!("test_files/day_10_test.txt"),
+            Day::D11 => include_str!("test_files/day_11_test.txt"),
+            Day::D12 => include_str!("test_files/day_12_test.txt"),
+            Day::D13 => include_str!("test_files/day_13_test.txt"),
+            Day::D14 => include_str!("test_files/day_14_test.txt"),
+            Day::D15 => include_str!("test_files/day_15_test.txt"),
+        }
+    }
+
+    pub fn bundled(self, sample: bool) -> &'static str {
+        if sample {
+            self.sample()
+        } else {
+            self.input()
+        }
+    }
+
+    /// The sample with `--sample` on the command line, otherwise the full
+    /// input.
+    pub fn input_for(self, args: &[String]) -> &'static str {
+        self.bundled(wants_sample(args))
+    }
+
+    /// The day's own `describe`. Days are modules rather than types, so
+    /// each has a free `describe` function instead of a trait impl.
+    pub fn describe(self) -> DayInfo {
+        match self {
+            Day::D1 => day_1::describe(),
+            Day::D2 => day_2::describe(),
+            Day::D3 => day_3::describe(),
+            Day::D4 => day_4::describe(),
+            Day::D5 => day_5::describe(),
+            Day::D6 => day_6::describe(),
+            Day::D7 => day_7::describe(),
+            Day::D8 => day_8::describe(),
+            Day::D9 => day_9::describe(),
+            Day::D10 => day_10::describe(),
+            Day::D11 => day_11::describe(),
+            Day::D12 => day_12::describe(),
+            Day::D13 => day_13::describe(),
+            Day::D14 => day_14::describe(),
+            Day::D15 => day_15::describe(),
+        }
+    }
+
+    /// Solves `part` (1 or 2) of this day for `input`.
+    pub fn run(self, part: u8, input: &str) -> Result<Answer> {
+        if !(1..=2).contains(&part) {
+            bail!("there is no part {part}, only 1 and 2");
+        }
+        let (_, solvers) = SOLVERS[usize::from(self.number() - 1)];
+        solvers[usize::from(part - 1)](input)
+    }
+}
+
+/// Solves one part of a day's puzzle for the given input.
+pub type Solver = fn(&str) -> Result<Answer>;
+
+/// Pairs each day with its module's `solve_part1` and `solve_part2`.
+macro_rules! register {
+    ($($day:ident => $module:ident),* $(,)?) => {
+        [$((Day::$day, [$module::solve_part1 as Solver, $module::solve_part2])),*]
+    };
+}
+
+/// Every day, in order.
+pub const SOLVERS: [(Day, [Solver; 2]); 15] = register![
+    D1 => day_1,
+    D2 => day_2,
+    D3 => day_3,
+    D4 => day_4,
+    D5 => day_5,
+    D6 => day_6,
+    D7 => day_7,
+    D8 => day_8,
+    D9 => day_9,
+    D10 => day_10,
+    D11 => day_11,
+    D12 => day_12,
+    D13 => day_13,
+    D14 => day_14,
+    D15 => day_15,
+];
+
+/// Whether `--sample` is on the command line.
+pub fn wants_sample(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "--sample")
+}
+
+impl fmt::Display for Day {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {}", self.number())
+    }
+}
+
+impl FromStr for Day {
+    type Err = anyhow::Error;
+
+    /// Accepts `7`, `day7` and `day_7`.
+    fn from_str(s: &str) -> Result<Self> {
+        let lower = s.to_ascii_lowercase();
+        let digits = match lower.strip_prefix("day") {
+            Some(rest) => rest.strip_prefix('_').unwrap_or(rest),
+            None => &lower,
+        };
+        digits
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| Day::ALL.get(n.checked_sub(1)?))
+            .copied()
+            .ok_or_else(|| anyhow!("unknown day {s:?}"))
+    }
+}
+
+#[test]
+fn test_parse_day() {
+    assert_eq!("day_13".parse::<Day>().unwrap(), Day::D13);
+    assert_eq!("day7".parse::<Day>().unwrap(), Day::D7);
+    assert_eq!("7".parse::<Day>().unwrap(), Day::D7);
+    assert_eq!("Day_1".parse::<Day>().unwrap(), Day::D1);
+    for bad in ["0", "16", "day", "day_x", "_7"] {
+        assert!(bad.parse::<Day>().is_err(), "{bad}");
+    }
+    for day in Day::ALL {
+        assert_eq!(day.number().to_string().parse::<Day>().unwrap(), day);
+    }
+}
+
+#[test]
+fn test_describe() {
+    let info = Day::D13.describe();
+    assert_eq!(info.answers, [AnswerKind::Int, AnswerKind::Int]);
+    assert!(info.title.contains("packets"));
+    assert_eq!(Day::D10.describe().answers[1], AnswerKind::Text);
+    for day in Day::ALL {
+        assert!(!day.describe().title.contains('\n'));
+    }
+}
+
+#[test]
+fn test_run() {
+    assert_eq!(
+        Day::D6.run(1, "mjqjpqmgbljsphdztnvjfqwrcgjmlb").unwrap(),
+        Answer::Int(7)
+    );
+    assert_eq!(
+        Day::D6.run(2, "mjqjpqmgbljsphdztnvjfqwrcgjmlb").unwrap(),
+        Answer::Int(19)
+    );
+    assert_eq!(
+        Day::D13
+            .run(2, include_str!("test_files/day_13_test.txt"))
+            .unwrap(),
+        Answer::Int(140)
+    );
+    assert!(Day::D6.run(3, "").is_err());
+    assert_eq!(
+        Day::D9
+            .run(1, "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2")
+            .unwrap(),
+        Answer::Int(13)
+    );
+}
+
+#[test]
+fn test_solvers() {
+    let expected = [
+        (Day::D1, ["24000", "45000"]),
+        (Day::D2, ["15", "12"]),
+        (Day::D3, ["157", "70"]),
+        (Day::D4, ["2", "4"]),
+        (Day::D5, ["CMZ", "MCD"]),
+        (Day::D7, ["95437", "24933642"]),
+        (Day::D8, ["21", "8"]),
+        (Day::D9, ["13", "1"]),
+        (Day::D11, ["10605", "2713310158"]),
+        (Day::D12, ["31", "29"]),
+        (Day::D13, ["13", "140"]),
+        (Day::D14, ["24", "93"]),
+    ];
+    assert_eq!(SOLVERS.map(|(day, _)| day), Day::ALL);
+    for (day, [part_1, part_2]) in SOLVERS {
+        let sample = day.sample();
+        let answers = [part_1(sample).unwrap(), part_2(sample).unwrap()];
+        for (part, answer) in [1, 2].into_iter().zip(&answers) {
+            assert_eq!(day.run(part, sample).unwrap(), *answer, "{day} part {part}");
+        }
+        if let Some((_, want)) = expected.iter().find(|(d, _)| *d == day) {
+            assert!(answers[0].matches(want[0]), "{day}: {}", answers[0]);
+            assert!(answers[1].matches(want[1]), "{day}: {}", answers[1]);
+        }
+    }
+    assert_eq!(
+        day_6::solve_part1(Day::D6.sample()).unwrap(),
+        Answer::Int(7)
+    );
+}
@@ -1,33 +1,103 @@
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::io::{self, BufRead};
 
-fn part_1(elf_carry_load: &Vec<u32>) {
-    println!("Part 1: {}", elf_carry_load.iter().max().unwrap());
+use anyhow::anyhow;
+
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::non_empty,
+    part::{run_day, Part},
+    util::{top_k, TopK},
+};
+
+pub(crate) fn parse(input: &str) -> anyhow::Result<Vec<u32>> {
+    let Some(input) = non_empty(input) else {
+        return Ok(vec![]);
+    };
+
+    input
+        .lines()
+        .enumerate()
+        .collect::<Vec<_>>()
+        .split(|(_, line)| line.is_empty())
+        .map(|group| {
+            group
+                .iter()
+                .map(|(i, v)| v.parse::<u32>().map_err(|e| anyhow!("line {}: {e}", i + 1)))
+                .sum()
+        })
+        .collect()
+}
+
+pub(crate) fn part_1(elf_carry_load: &[u32]) -> u32 {
+    elf_carry_load.iter().copied().max().unwrap_or(0)
+}
+
+pub(crate) fn part_2(elf_carry_load: &[u32]) -> u32 {
+    top_k(elf_carry_load.iter().copied(), 3).iter().sum()
 }
 
-fn part_2(elf_carry_load: &Vec<u32>) {
-    let mut heap = BinaryHeap::new();
-    for item in elf_carry_load.iter() {
-        heap.push(Reverse(item));
-        if heap.len() > 3 {
-            heap.pop();
+/// Solves both parts in one pass over `reader`. Only the running sum of
+/// the current group and a heap of the three largest totals are kept, so
+/// memory doesn't grow with the number of elves.
+fn solve_streaming(reader: impl BufRead) -> io::Result<(u32, u32)> {
+    let mut top = TopK::new(3);
+    let mut group = None;
+    let mut finish_group = |group: Option<u32>| {
+        if let Some(total) = group {
+            top.push(total);
         }
+    };
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            finish_group(group.take());
+            continue;
+        }
+
+        let calories = line.parse::<u32>().map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1))
+        })?;
+        group = Some(group.unwrap_or(0) + calories);
     }
+    finish_group(group);
 
-    println!(
-        "Part 2: {:?}",
-        heap.into_iter().map(|rev| rev.0).sum::<u32>()
-    );
+    let top = top.into_sorted_vec();
+    Ok((top.first().copied().unwrap_or(0), top.iter().sum()))
 }
 
-fn main() {
-    let elf_carry_load = include_str!("test_files/day_1.txt")
-        .lines()
-        .collect::<Vec<_>>()
-        .split(|line| line.is_empty())
-        .map(|group| group.iter().map(|v| v.parse::<u32>().unwrap()).sum())
-        .collect();
-    part_1(&elf_carry_load);
-    part_2(&elf_carry_load);
+pub fn solve_part1(input: &str) -> anyhow::Result<Answer> {
+    Ok(part_1(&parse(input)?).into())
+}
+
+pub fn solve_part2(input: &str) -> anyhow::Result<Answer> {
+    Ok(part_2(&parse(input)?).into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Calorie Counting: sums the snacks each elf carries and finds the heaviest loads",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
+
+pub fn main() -> anyhow::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--stdin") {
+        let (part_1, part_2) = solve_streaming(io::stdin().lock())?;
+        println!("Part 1: {part_1}");
+        println!("Part 2: {part_2}");
+        return Ok(());
+    }
+
+    run_day(
+        Part::from_args(&args)?,
+        |i| Ok(part_1(&parse(i)?)),
+        |i| Ok(part_2(&parse(i)?)),
+        Day::D1.input_for(&args),
+    )
 }
 
 #[test]
@@ -41,3 +111,34 @@ fn test_part_1() {
 
     assert_eq!(&24000, elf_carry_load.iter().max().unwrap());
 }
+
+#[test]
+fn test_empty_input() {
+    for input in ["", "  \n"] {
+        let elf_carry_load = parse(input).unwrap();
+        assert_eq!(part_1(&elf_carry_load), 0);
+        assert_eq!(part_2(&elf_carry_load), 0);
+    }
+}
+
+#[test]
+fn test_solve_streaming() {
+    let input = include_str!("test_files/day_1_test.txt");
+    let loads = parse(input).unwrap();
+    let streamed = solve_streaming(io::Cursor::new(input)).unwrap();
+    assert_eq!(streamed, (part_1(&loads), part_2(&loads)));
+    assert_eq!(streamed, (24000, 45000));
+
+    assert_eq!(solve_streaming(io::Cursor::new("")).unwrap(), (0, 0));
+    assert!(solve_streaming(io::Cursor::new("1\nx\n")).is_err());
+    let err = parse("1\n\n2\nx\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 4: invalid digit found in string");
+
+    // 100k elves carrying 1..=100000 calories each; only the last three
+    // totals should survive.
+    let big = (1..=100_000)
+        .map(|n| format!("{n}\n\n"))
+        .collect::<String>();
+    let streamed = solve_streaming(io::Cursor::new(big)).unwrap();
+    assert_eq!(streamed, (100_000, 100_000 + 99_999 + 99_998));
+}
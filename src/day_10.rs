@@ -1,4 +1,10 @@
-use anyhow::Result;
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{is_significant, non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{anyhow, Result};
 use core::fmt;
 use nom::{
     branch::alt,
@@ -7,7 +13,7 @@ use nom::{
     sequence::preceded,
     Finish, IResult,
 };
-use std::collections::VecDeque;
+use std::io::{self, BufRead};
 
 const DISPLAY_MASK: u64 = 0b1111111111111111111111111111111111111111;
 
@@ -41,39 +47,115 @@ impl From<Instruction> for i32 {
     }
 }
 
-struct CPU {
-    instructions: VecDeque<Instruction>,
+/// Parses a program one line at a time, as the CPU asks for the next
+/// instruction.
+struct Program<R> {
+    lines: io::Lines<R>,
+    line_no: usize,
+}
+
+impl<R: BufRead> Iterator for Program<R> {
+    type Item = io::Result<Instruction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = loop {
+            let line = self.lines.next()?;
+            self.line_no += 1;
+            if line.as_ref().map_or(true, |line| is_significant(line)) {
+                break line;
+            }
+        };
+        Some(line.and_then(|line| {
+            all_consuming(Instruction::parse)(&line)
+                .finish()
+                .map(|(_, inst)| inst)
+                .map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: invalid instruction {line:?}", self.line_no),
+                    )
+                })
+        }))
+    }
+}
+
+struct Cpu {
+    instructions: Box<dyn Iterator<Item = io::Result<Instruction>>>,
     current_inst: Option<(Instruction, u32)>,
+    error: Option<io::Error>,
     x_reg: i32,
-    cycle: u32,
+    cycle: u64,
+    /// The CRT line `display[0]` holds, which is only past 0 after
+    /// `fast_forward`.
+    first_line: u64,
     display: Vec<u64>,
 }
 
-impl CPU {
-    fn from_str(i: &str) -> Self {
+impl Cpu {
+    fn from_str(i: &str) -> Result<Self> {
+        let instructions = significant_lines(non_empty(i).unwrap_or_default())
+            .map(|(n, line)| {
+                let parsed = all_consuming(Instruction::parse)(line).finish();
+                parsed
+                    .map(|(_, inst)| Ok(inst))
+                    .map_err(|_| anyhow!("line {n}: invalid instruction {line:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_instructions(Box::new(instructions.into_iter())))
+    }
+
+    /// Streams the program from `r` instead of parsing it up front. A bad
+    /// first line is reported here; later ones stop the CPU and are reported
+    /// by `check_error`.
+    fn from_reader(r: impl BufRead + 'static) -> io::Result<Self> {
+        let mut cpu = Self::from_instructions(Box::new(Program {
+            lines: r.lines(),
+            line_no: 0,
+        }));
+        cpu.check_error()?;
+        Ok(cpu)
+    }
+
+    fn from_instructions(instructions: Box<dyn Iterator<Item = io::Result<Instruction>>>) -> Self {
         let mut x = Self {
-            instructions: i
-                .lines()
-                .map(|line| all_consuming(Instruction::parse)(line).finish().unwrap().1)
-                .collect(),
+            instructions,
             current_inst: None,
+            error: None,
             x_reg: 1,
             cycle: 0,
+            first_line: 0,
             display: vec![],
         };
         x.set_inst();
         x
     }
 
+    fn check_error(&mut self) -> io::Result<()> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+
     fn set_inst(&mut self) {
-        self.current_inst = self
-            .instructions
-            .pop_front()
-            .map(|inst| (inst, inst.cycles()));
+        self.current_inst = match self.instructions.next() {
+            Some(Ok(inst)) => Some((inst, inst.cycles())),
+            Some(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            None => None,
+        };
+    }
+
+    /// Moves the clock on by `cycles` without running anything, as if the
+    /// CPU had sat idle that long. The lines it skips over aren't kept.
+    #[cfg(test)]
+    fn fast_forward(&mut self, cycles: u64) {
+        self.cycle += cycles;
+        self.first_line = self.cycle / 40;
+        self.display.clear();
     }
 
     fn draw(&mut self) {
-        let line = (self.cycle / 40) as usize;
+        let line = (self.cycle / 40 - self.first_line) as usize;
         if line + 1 > self.display.len() {
             self.display.push(0);
         }
@@ -103,7 +185,7 @@ impl CPU {
     }
 }
 
-impl fmt::Debug for CPU {
+impl fmt::Debug for Cpu {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for line in &self.display {
             for i in 0..40 {
@@ -128,57 +210,245 @@ fn sprite_value(pos: i32) -> u64 {
     shft & DISPLAY_MASK
 }
 
-fn cycle_mask(cycle: u32) -> u64 {
+fn cycle_mask(cycle: u64) -> u64 {
     (0b1000000000000000000000000000000000000000 >> (cycle % 40)) & DISPLAY_MASK
 }
 
-fn part_1(input: &str) -> i32 {
-    let mut cpu = CPU::from_str(input);
+/// Runs the program to the end, drawing every cycle, and calls `on_cycle`
+/// with each 1-based cycle number and the X register during that cycle.
+fn run_with(cpu: &mut Cpu, mut on_cycle: impl FnMut(u64, i32)) {
+    while cpu.current_inst.is_some() {
+        on_cycle(cpu.cycle + 1, cpu.x_reg);
+        cpu.draw();
+        cpu.step();
+    }
+}
+
+/// Runs the program to the end and returns the total signal strength.
+fn run(cpu: &mut Cpu) -> i32 {
     let mut total = 0;
     let count = [20, 60, 100, 140, 180, 220];
 
-    while cpu.step() {
-        if count.contains(&(cpu.cycle + 1)) {
-            total += (cpu.cycle as i32 + 1) * cpu.x_reg;
+    run_with(cpu, |cycle, x_reg| {
+        if count.contains(&cycle) {
+            total += cycle as i32 * x_reg;
         }
-    }
+    });
 
     total
 }
 
-fn part_2(input: &str) -> String {
-    let mut cpu = CPU::from_str(input);
+/// Every cycle's `(cycle, x_reg, signal_strength)`.
+fn signal_trace(input: &str) -> Result<Vec<(u64, i32, i64)>> {
+    let mut trace = vec![];
+    run_with(&mut Cpu::from_str(input)?, |cycle, x_reg| {
+        trace.push((cycle, x_reg, cycle as i64 * x_reg as i64))
+    });
+    Ok(trace)
+}
 
-    while cpu.step() {
-        cpu.draw();
+pub(crate) fn part_1(input: &str) -> Result<i32> {
+    Ok(run(&mut Cpu::from_str(input)?))
+}
+
+pub(crate) fn part_2(input: &str) -> Result<String> {
+    let mut cpu = Cpu::from_str(input)?;
+    run(&mut cpu);
+
+    Ok(format!("{cpu:?}"))
+}
+
+/// The CRT after the puzzle input, one pixel per CRT pixel.
+pub(crate) fn thumbnail() -> egui::ColorImage {
+    let mut cpu = Cpu::from_str(include_str!("test_files/day_10.txt"))
+        .expect("the puzzle input should parse");
+    run(&mut cpu);
+    let pixels = cpu
+        .display
+        .iter()
+        .flat_map(|&line| (0..40).map(move |i| line & cycle_mask(i) > 0))
+        .map(|lit| {
+            if lit {
+                egui::Color32::from_rgb(20, 255, 20)
+            } else {
+                egui::Color32::from_gray(27)
+            }
+        })
+        .collect();
+    egui::ColorImage {
+        size: [40, cpu.display.len()],
+        pixels,
     }
+}
 
-    format!("{cpu:?}")
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(input)?.into())
 }
 
-fn main() -> Result<()> {
-    let input = &include_str!("test_files/day_10.txt");
-    println!("Part 1: {}", part_1(input));
-    println!("Part 2:\n{}", part_2(input));
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(input)?.into())
+}
 
-    Ok(())
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Cathode-Ray Tube: runs a tiny CPU and draws the letters on its CRT",
+        answers: [AnswerKind::Int, AnswerKind::Text],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--stdin") {
+        let mut cpu = Cpu::from_reader(io::stdin().lock())?;
+        let part_1 = run(&mut cpu);
+        cpu.check_error()?;
+        println!("Part 1: {part_1}");
+        println!("Part 2:\n{cpu:?}");
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--csv") {
+        println!("cycle,x_reg,signal_strength");
+        for (cycle, x_reg, strength) in signal_trace(Day::D10.input_for(&args))? {
+            println!("{cycle},{x_reg},{strength}");
+        }
+        return Ok(());
+    }
+
+    run_day(
+        Part::from_args(&args)?,
+        part_1,
+        part_2,
+        Day::D10.input_for(&args),
+    )
 }
 
 #[test]
 fn test_basic_step() {
     let input = "noop\naddx 3\naddx -5";
-    let mut cpu = CPU::from_str(input);
+    let mut cpu = Cpu::from_str(input).unwrap();
 
     while cpu.step() {}
 
     assert_eq!(cpu.x_reg, -1);
+
+    let err = Cpu::from_str("noop\n# comment\nbogus 3\nnoop")
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "line 3: invalid instruction \"bogus 3\"");
+    assert!(part_1("addx").unwrap_err().to_string().contains("line 1"));
+    assert!(part_2("noop\naddx x")
+        .unwrap_err()
+        .to_string()
+        .contains("line 2"));
 }
 
 #[test]
 fn test_part_1() {
     let input = "addx 15\r\naddx -11\r\naddx 6\r\naddx -3\r\naddx 5\r\naddx -1\r\naddx -8\r\naddx 13\r\naddx 4\r\nnoop\r\naddx -1\r\naddx 5\r\naddx -1\r\naddx 5\r\naddx -1\r\naddx 5\r\naddx -1\r\naddx 5\r\naddx -1\r\naddx -35\r\naddx 1\r\naddx 24\r\naddx -19\r\naddx 1\r\naddx 16\r\naddx -11\r\nnoop\r\nnoop\r\naddx 21\r\naddx -15\r\nnoop\r\nnoop\r\naddx -3\r\naddx 9\r\naddx 1\r\naddx -3\r\naddx 8\r\naddx 1\r\naddx 5\r\nnoop\r\nnoop\r\nnoop\r\nnoop\r\nnoop\r\naddx -36\r\nnoop\r\naddx 1\r\naddx 7\r\nnoop\r\nnoop\r\nnoop\r\naddx 2\r\naddx 6\r\nnoop\r\nnoop\r\nnoop\r\nnoop\r\nnoop\r\naddx 1\r\nnoop\r\nnoop\r\naddx 7\r\naddx 1\r\nnoop\r\naddx -13\r\naddx 13\r\naddx 7\r\nnoop\r\naddx 1\r\naddx -33\r\nnoop\r\nnoop\r\nnoop\r\naddx 2\r\nnoop\r\nnoop\r\nnoop\r\naddx 8\r\nnoop\r\naddx -1\r\naddx 2\r\naddx 1\r\nnoop\r\naddx 17\r\naddx -9\r\naddx 1\r\naddx 1\r\naddx -3\r\naddx 11\r\nnoop\r\nnoop\r\naddx 1\r\nnoop\r\naddx 1\r\nnoop\r\nnoop\r\naddx -13\r\naddx -19\r\naddx 1\r\naddx 3\r\naddx 26\r\naddx -30\r\naddx 12\r\naddx -1\r\naddx 3\r\naddx 1\r\nnoop\r\nnoop\r\nnoop\r\naddx -9\r\naddx 18\r\naddx 1\r\naddx 2\r\nnoop\r\nnoop\r\naddx 9\r\nnoop\r\nnoop\r\nnoop\r\naddx -1\r\naddx 2\r\naddx -37\r\naddx 1\r\naddx 3\r\nnoop\r\naddx 15\r\naddx -21\r\naddx 22\r\naddx -6\r\naddx 1\r\nnoop\r\naddx 2\r\naddx 1\r\nnoop\r\naddx -10\r\nnoop\r\nnoop\r\naddx 20\r\naddx 1\r\naddx 2\r\naddx 2\r\naddx -6\r\naddx -11\r\nnoop\r\nnoop\r\nnoop";
 
-    let output = part_1(input);
+    let output = part_1(input).unwrap();
 
     assert_eq!(output, 13140);
 }
+
+#[test]
+fn test_from_reader() {
+    let input = include_str!("test_files/day_10_test.txt");
+    let history = |mut cpu: Cpu| {
+        let mut x = vec![cpu.x_reg];
+        while cpu.step() {
+            x.push(cpu.x_reg);
+        }
+        (x, cpu.check_error().is_ok())
+    };
+
+    let streamed = history(Cpu::from_reader(io::Cursor::new(input)).unwrap());
+    assert_eq!(streamed, history(Cpu::from_str(input).unwrap()));
+    assert_eq!(streamed.0.len(), 241);
+
+    let mut cpu = Cpu::from_reader(io::Cursor::new("noop\naddx 3\naddx\nnoop")).unwrap();
+    while cpu.step() {}
+    assert_eq!(cpu.x_reg, 4);
+    let err = cpu.check_error().unwrap_err();
+    assert_eq!(err.to_string(), "line 3: invalid instruction \"addx\"");
+
+    assert!(Cpu::from_reader(io::Cursor::new("nope\n")).is_err());
+}
+
+#[test]
+fn test_crt_golden() {
+    let input = include_str!("test_files/day_10_test.txt");
+    crate::golden::assert_golden("day_10_crt.txt", &part_2(input).unwrap());
+}
+
+#[test]
+fn test_comments() {
+    let clean = include_str!("test_files/day_10_test.txt");
+    let annotated = clean
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i % 7 {
+            0 => format!("# instruction {i}\n{line}"),
+            3 => format!("{line}\n\n"),
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let x_reg = |mut cpu: Cpu| {
+        while cpu.step() {}
+        cpu.x_reg
+    };
+    assert_eq!(
+        x_reg(Cpu::from_str(&annotated).unwrap()),
+        x_reg(Cpu::from_str(clean).unwrap())
+    );
+    assert_eq!(part_1(&annotated).unwrap(), 13140);
+
+    let streamed = Cpu::from_reader(io::Cursor::new(annotated)).unwrap();
+    assert_eq!(x_reg(streamed), x_reg(Cpu::from_str(clean).unwrap()));
+
+    let mut cpu = Cpu::from_reader(io::Cursor::new("# start\nnoop\n\naddx")).unwrap();
+    while cpu.step() {}
+    let err = cpu.check_error().unwrap_err();
+    assert_eq!(err.to_string(), "line 4: invalid instruction \"addx\"");
+}
+
+#[test]
+fn test_signal_trace() {
+    let trace = signal_trace(include_str!("test_files/day_10_test.txt")).unwrap();
+    assert_eq!(trace.len(), 240);
+    assert_eq!(trace[0], (1, 1, 1));
+    assert_eq!(trace[19], (20, 21, 420));
+
+    let sampled = trace
+        .iter()
+        .filter(|(cycle, ..)| cycle % 40 == 20)
+        .map(|&(_, _, strength)| strength)
+        .sum::<i64>();
+    assert_eq!(sampled, 13140);
+}
+
+#[test]
+fn test_past_u32_cycles() {
+    let input = include_str!("test_files/day_10_test.txt");
+    let expected = signal_trace(input).unwrap();
+
+    let skipped = u32::MAX as u64 - 100;
+    let mut cpu = Cpu::from_str(input).unwrap();
+    cpu.fast_forward(skipped);
+    let mut trace = vec![];
+    run_with(&mut cpu, |cycle, x_reg| trace.push((cycle, x_reg)));
+
+    assert_eq!(trace.len(), expected.len());
+    for (&(cycle, x_reg), &(expected_cycle, expected_x, _)) in trace.iter().zip(&expected) {
+        assert_eq!((cycle, x_reg), (expected_cycle + skipped, expected_x));
+    }
+    assert!(cpu.cycle > u32::MAX as u64);
+    assert_eq!(cpu.x_reg, 17);
+    assert_eq!(
+        cpu.first_line + cpu.display.len() as u64 - 1,
+        cpu.cycle / 40
+    );
+}
@@ -1,5 +1,18 @@
-use anyhow::Result;
-use itertools::Itertools;
+use core::fmt;
+use std::{
+    iter::Product,
+    ops::{Div, Rem},
+    str::FromStr,
+};
+
+use crate::{
+    answer::Answer,
+    budget::{Budget, Timeout},
+    day::{AnswerKind, Day, DayInfo},
+    part::{run_day, Part},
+    util::{gcd, top_k},
+};
+use anyhow::{anyhow, bail, Result};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -10,6 +23,33 @@ use nom::{
     Finish, IResult,
 };
 
+/// The arithmetic a worry level needs. Adding and multiplying are checked
+/// so that running without relief reports an overflow instead of wrapping.
+pub(crate) trait Worry:
+    Copy + fmt::Debug + From<u64> + PartialEq + Div<Output = Self> + Rem<Output = Self> + Product
+{
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_worry {
+    ($($t:ty),*) => {
+        $(
+            impl Worry for $t {
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_add(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_worry!(u64, u128);
+
 #[derive(Debug, Clone, Copy)]
 enum Term {
     Old,
@@ -17,10 +57,10 @@ enum Term {
 }
 
 impl Term {
-    fn value(self, old: u64) -> u64 {
+    fn value<N: Worry>(self, old: N) -> N {
         match self {
             Term::Old => old,
-            Term::Const(val) => val,
+            Term::Const(val) => val.into(),
         }
     }
 }
@@ -32,24 +72,49 @@ enum Operation {
 }
 
 impl Operation {
-    fn eval(self, old: u64) -> u64 {
+    /// `None` if the new worry level overflows.
+    fn eval<N: Worry>(self, old: N) -> Option<N> {
         match self {
-            Operation::Add(l, r) => l.value(old) + r.value(old),
-            Operation::Mult(l, r) => l.value(old) * r.value(old),
+            Operation::Add(l, r) => l.value(old).checked_add(r.value(old)),
+            Operation::Mult(l, r) => l.value(old).checked_mul(r.value(old)),
         }
     }
 }
 
+/// Input worry levels always fit in a `u64`, so monkeys are parsed with
+/// one and `widen`ed for inputs that need more room.
 #[derive(Debug, Clone)]
-struct Monkey {
+pub(crate) struct Monkey<N = u64> {
     items_inspected: u64,
-    items: Vec<u64>,
+    items: Vec<N>,
     operation: Operation,
     divisor: u64,
     receiver_if_true: usize,
     receiver_if_false: usize,
 }
 
+impl<N: Worry> Monkey<N> {
+    fn widen<M: Worry + From<N>>(&self) -> Monkey<M> {
+        Monkey {
+            items_inspected: self.items_inspected,
+            items: self.items.iter().map(|&i| M::from(i)).collect(),
+            operation: self.operation,
+            divisor: self.divisor,
+            receiver_if_true: self.receiver_if_true,
+            receiver_if_false: self.receiver_if_false,
+        }
+    }
+
+    /// The monkey an item with this worry level gets thrown to.
+    fn target(&self, worry: N) -> usize {
+        if worry % N::from(self.divisor) == N::from(0) {
+            self.receiver_if_true
+        } else {
+            self.receiver_if_false
+        }
+    }
+}
+
 fn parse_term(i: &str) -> IResult<&str, Term> {
     alt((
         value(Term::Old, tag("old")),
@@ -117,11 +182,92 @@ fn parse_monkey(i: &str) -> IResult<&str, Monkey> {
     ))
 }
 
-fn parse_all_monkeys(i: &str) -> IResult<&str, Vec<Monkey>> {
+pub(crate) fn parse_all_monkeys(i: &str) -> IResult<&str, Vec<Monkey>> {
     separated_list1(nom::character::complete::multispace1, parse_monkey)(i)
 }
 
-fn round_part_1(m: &mut [Monkey]) {
+/// A monkey block that `parse_monkeys_lenient` had to skip.
+#[derive(Debug, PartialEq, Eq)]
+struct ParseWarning {
+    block: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipped block {}: {}", self.block, self.message)
+    }
+}
+
+/// Parses each blank-line separated block on its own, skipping the ones that
+/// do not parse instead of failing the whole input. Monkeys after a skipped
+/// block shift down by one, so their receivers may no longer line up.
+fn parse_monkeys_lenient(i: &str) -> (Vec<Monkey>, Vec<ParseWarning>) {
+    let mut monkeys = vec![];
+    let mut warnings = vec![];
+
+    let blocks = i.split("\n\n").map(str::trim).filter(|b| !b.is_empty());
+    for (block, text) in blocks.enumerate() {
+        let text = format!("{text}\n");
+        let parsed = all_consuming(parse_monkey)(&text).finish();
+        match parsed {
+            Ok((_, monkey)) => monkeys.push(monkey),
+            Err(e) => warnings.push(ParseWarning {
+                block,
+                message: format!(
+                    "{:?} failed at {:?}",
+                    e.code,
+                    e.input.lines().next().unwrap_or_default().trim()
+                ),
+            }),
+        }
+    }
+
+    (monkeys, warnings)
+}
+
+/// How worry levels are kept in check after each inspection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Relief {
+    /// Part 1: worry is divided by three.
+    DivideByThree,
+    /// Part 2: no relief, but worry is kept modulo the least common
+    /// multiple of the divisors, which none of the monkeys' tests can tell
+    /// apart.
+    Modulo,
+    /// No relief at all. Worry grows without bound, so any input where a
+    /// monkey squares it overflows within a handful of rounds.
+    None,
+}
+
+impl FromStr for Relief {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "three" => Ok(Relief::DivideByThree),
+            "modulo" => Ok(Relief::Modulo),
+            "none" => Ok(Relief::None),
+            _ => bail!("unknown relief {s:?}, expected three, modulo or none"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Overflow {
+    round: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "worry level overflowed in round {}", self.round)
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+/// Plays one round, or `None` if a worry level overflows part way through.
+fn round<N: Worry>(m: &mut [Monkey<N>], relief: Relief, modulus: N) -> Option<()> {
     let monkey_count = m.len();
 
     for i in 0..monkey_count {
@@ -132,97 +278,388 @@ fn round_part_1(m: &mut [Monkey]) {
             monkey.items_inspected += mc.items.len() as u64;
         }
 
-        for mut item in mc.items.iter().copied() {
-            item = mc.operation.eval(item);
-            item /= 3;
+        for item in mc.items.iter().copied() {
+            let item = match relief {
+                Relief::DivideByThree => mc.operation.eval(item)? / N::from(3),
+                Relief::Modulo => mc.operation.eval(item % modulus)?,
+                Relief::None => mc.operation.eval(item)?,
+            };
 
-            if item % mc.divisor == 0 {
-                m[mc.receiver_if_true].items.push(item);
-            } else {
-                m[mc.receiver_if_false].items.push(item);
-            }
+            m[mc.target(item)].items.push(item);
         }
 
         m[i].items.clear();
     }
+
+    Some(())
 }
 
-fn round_part_2(m: &mut [Monkey], divisors: u64) {
-    let monkey_count = m.len();
+/// Monkeys that have been checked to be able to play: every divisor is
+/// non-zero and every throw lands on a monkey that exists.
+#[derive(Debug, Clone)]
+pub(crate) struct Troop<N = u64> {
+    monkeys: Vec<Monkey<N>>,
+    /// The least common multiple of the divisors, worked out once since
+    /// every round under `Relief::Modulo` needs it.
+    lcm: u64,
+}
 
-    for i in 0..monkey_count {
-        let mc;
+impl<N: Worry> Troop<N> {
+    pub(crate) fn new(monkeys: Vec<Monkey<N>>) -> Result<Self> {
+        let count = monkeys.len();
+        for (i, m) in monkeys.iter().enumerate() {
+            if m.divisor == 0 {
+                bail!("monkey {i} tests divisibility by zero");
+            }
+            if let Some(to) = [m.receiver_if_true, m.receiver_if_false]
+                .into_iter()
+                .find(|&to| to >= count)
+            {
+                bail!("monkey {i} throws to monkey {to}, which doesn't exist");
+            }
+        }
+        let lcm = monkeys
+            .iter()
+            .try_fold(1u64, |lcm, m| {
+                (lcm / gcd(lcm, m.divisor)).checked_mul(m.divisor)
+            })
+            .ok_or_else(|| anyhow!("the divisors' least common multiple overflows"))?;
+        // Under `Relief::Modulo` worry is below the lcm before each
+        // operation, and every operation grows with worry, so checking the
+        // largest such value covers them all.
+        if let Some(i) = monkeys
+            .iter()
+            .position(|m| m.operation.eval(N::from(lcm - 1)).is_none())
         {
-            let monkey = &mut m[i];
-            mc = monkey.clone();
-            monkey.items_inspected += mc.items.len() as u64;
+            bail!("monkey {i}'s worry can overflow even kept below the divisors' lcm of {lcm}");
         }
 
-        for mut item in mc.items.iter().copied() {
-            item %= divisors;
-            item = mc.operation.eval(item);
+        Ok(Self { monkeys, lcm })
+    }
 
-            if item % mc.divisor == 0 {
-                m[mc.receiver_if_true].items.push(item);
-            } else {
-                m[mc.receiver_if_false].items.push(item);
-            }
+    fn widen<M: Worry + From<N>>(&self) -> Troop<M> {
+        Troop {
+            monkeys: self.monkeys.iter().map(Monkey::widen).collect(),
+            lcm: self.lcm,
         }
+    }
 
-        m[i].items.clear();
+    /// The monkeys after `rounds` rounds with the given relief.
+    fn play(&self, rounds: usize, relief: Relief) -> Result<Vec<Monkey<N>>, Overflow> {
+        let mut m = self.monkeys.clone();
+        for r in 1..=rounds {
+            round(&mut m, relief, N::from(self.lcm)).ok_or(Overflow { round: r })?;
+        }
+
+        Ok(m)
+    }
+
+    /// The product of the two highest inspection counts after `rounds`
+    /// rounds.
+    pub(crate) fn monkey_business(&self, rounds: usize, relief: Relief) -> Result<u64, Overflow> {
+        Ok(Summary::new(&self.play(rounds, relief)?, rounds).monkey_business)
+    }
+
+    /// Runs `rounds` part 2 rounds, spending one step of `budget` per
+    /// round.
+    fn monkey_business_within(&self, rounds: usize, budget: &mut Budget) -> Result<u64, Timeout> {
+        let mut m = self.monkeys.clone();
+        for _ in 0..rounds {
+            budget.tick()?;
+            round(&mut m, Relief::Modulo, N::from(self.lcm))
+                .expect("Troop::new checked worry below the lcm can't overflow");
+        }
+
+        Ok(Summary::new(&m, rounds).monkey_business)
+    }
+}
+
+/// At-a-glance state of the monkeys after some number of rounds.
+#[derive(Debug, PartialEq, Eq)]
+struct Summary {
+    round: usize,
+    throws: u64,
+    monkey_business: u64,
+}
+
+impl Summary {
+    fn new<N>(m: &[Monkey<N>], round: usize) -> Self {
+        Self {
+            round,
+            throws: m.iter().map(|m| m.items_inspected).sum(),
+            monkey_business: top_k(m.iter().map(|m| m.items_inspected), 2)
+                .iter()
+                .product(),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Round {}: {} throws, monkey business {}",
+            self.round, self.throws, self.monkey_business
+        )
     }
 }
 
-fn part_1(m: &Vec<Monkey>) -> u64 {
-    let mut m = m.clone();
-    (0..20).for_each(|_| round_part_1(&mut m));
+pub(crate) fn part_1<N: Worry>(troop: &Troop<N>, relief: Relief) -> Result<u64, Overflow> {
+    troop.monkey_business(20, relief)
+}
+
+/// Part 1 on parsed monkeys. Without relief, worry outgrows a `u64` within
+/// a few rounds, so that is always played on `u128`.
+pub(crate) fn part_1_auto_width(troop: &Troop, relief: Relief) -> Result<u64, Overflow> {
+    match relief {
+        Relief::None => part_1(&troop.widen::<u128>(), relief),
+        _ => part_1(troop, relief),
+    }
+}
 
-    m.iter()
-        .map(|m| m.items_inspected)
-        .sorted_by_key(|&c| std::cmp::Reverse(c))
-        .take(2)
-        .product()
+pub(crate) fn part_2<N: Worry>(troop: &Troop<N>, budget: &mut Budget) -> Result<u64, Timeout> {
+    troop.monkey_business_within(10000, budget)
 }
 
-fn part_2(m: &Vec<Monkey>) -> u64 {
-    let mut m = m.clone();
-    let divisors = m.iter().map(|m| m.divisor).product::<u64>();
-    (0..10000).for_each(|_| round_part_2(&mut m, divisors));
+fn parse_input(input: &str) -> Result<Troop> {
+    let parsed = all_consuming(parse_all_monkeys)(input).finish();
+    let (_, monkeys) = parsed.map_err(|e| anyhow!("invalid input: {e}"))?;
+    Troop::new(monkeys)
+}
 
-    m.iter()
-        .map(|m| m.items_inspected)
-        .sorted_by_key(|&c| std::cmp::Reverse(c))
-        .take(2)
-        .product()
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(&parse_input(input)?, Relief::DivideByThree)?.into())
 }
 
-fn main() -> Result<()> {
-    let input = include_str!("test_files/day_11.txt");
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(&parse_input(input)?, &mut Budget::default())?.into())
+}
 
-    let monkeys = all_consuming(parse_all_monkeys)(&input).finish()?.1;
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Monkey in the Middle: simulates monkeys throwing items by worry level",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
 
-    println!("Part 1: {}", part_1(&monkeys));
-    println!("Part 2: {}", part_2(&monkeys));
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let input = Day::D11.input_for(&args);
 
-    Ok(())
+    let troop = if args.iter().any(|arg| arg == "--lenient") {
+        let (monkeys, warnings) = parse_monkeys_lenient(input);
+        for warning in &warnings {
+            eprintln!("warning: {warning}");
+        }
+        Troop::new(monkeys)?
+    } else {
+        parse_input(input)?
+    };
+    let mut budget = Budget::from_args(&args)?;
+    let relief = match args.iter().position(|arg| arg == "--relief") {
+        Some(i) => args
+            .get(i + 1)
+            .ok_or_else(|| anyhow::anyhow!("Usage: --relief three|modulo|none"))?
+            .parse()?,
+        None => Relief::DivideByThree,
+    };
+
+    let part = Part::from_args(&args)?;
+
+    if args.iter().any(|arg| arg == "--wide") {
+        let troop = troop.widen::<u128>();
+        run_day(
+            part,
+            |_| Ok(part_1(&troop, relief)?),
+            |_| Ok(part_2(&troop, &mut budget)?),
+            input,
+        )
+    } else {
+        run_day(
+            part,
+            |_| Ok(part_1_auto_width(&troop, relief)?),
+            |_| Ok(part_2(&troop, &mut budget)?),
+            input,
+        )
+    }
 }
 
 #[test]
-fn test_part_1() {
+fn test_target() {
+    let monkey: Monkey = Monkey {
+        items_inspected: 0,
+        items: vec![],
+        operation: Operation::Add(Term::Old, Term::Const(1)),
+        divisor: 13,
+        receiver_if_true: 1,
+        receiver_if_false: 3,
+    };
+    assert_eq!(monkey.target(26), 1);
+    assert_eq!(monkey.target(0), 1);
+    assert_eq!(monkey.target(27), 3);
+    assert_eq!(monkey.target(1), 3);
+    assert_eq!(monkey.widen::<u128>().target(13 * u128::from(u64::MAX)), 1);
+}
+
+#[cfg(test)]
+fn sample_troop() -> Troop {
     let input = include_str!("test_files/day_11_test.txt");
-    let monkeys = all_consuming(parse_all_monkeys)(&input).finish().unwrap().1;
+    Troop::new(all_consuming(parse_all_monkeys)(input).finish().unwrap().1).unwrap()
+}
 
-    let result = part_1(&monkeys);
+#[test]
+fn test_part_1() {
+    let result = part_1(&sample_troop(), Relief::DivideByThree).unwrap();
 
     assert_eq!(result, 10605);
 }
 
 #[test]
 fn test_part_2() {
+    let result = part_2(&sample_troop(), &mut Budget::default()).unwrap();
+
+    assert_eq!(result, 2713310158);
+}
+
+#[test]
+fn test_budget() {
+    let troop = sample_troop();
+
+    assert_eq!(
+        troop.monkey_business_within(10000, &mut Budget::steps(10)),
+        Err(Timeout)
+    );
+    assert_eq!(part_2(&troop, &mut Budget::steps(10000)), Ok(2713310158));
+}
+
+#[test]
+fn test_troop() {
+    let troop = sample_troop();
+    let divisors = troop.monkeys.iter().map(|m| m.divisor).collect::<Vec<_>>();
+    let manual = (1..).find(|n| divisors.iter().all(|d| n % d == 0));
+    assert_eq!(Some(troop.lcm), manual);
+
+    // Divisors that share factors: the lcm is smaller than their product.
+    let mut monkeys = troop.monkeys.clone();
+    for (m, divisor) in monkeys.iter_mut().zip([4, 6, 10, 15]) {
+        m.divisor = divisor;
+    }
+    assert_eq!(Troop::new(monkeys.clone()).unwrap().lcm, 60);
+
+    // Monkey 2 squares its worry, which no longer fits in a u64 once the
+    // lcm is past 2^32, though it still does in a u128.
+    monkeys[3].divisor = 1 << 33;
+    let err = Troop::new(monkeys.clone()).unwrap_err();
+    assert!(err.to_string().starts_with("monkey 2's worry can overflow"));
+    let wide = monkeys.iter().map(Monkey::widen).collect::<Vec<_>>();
+    assert!(Troop::<u128>::new(wide).is_ok());
+    monkeys[3].divisor = 15;
+
+    monkeys[2].divisor = 0;
+    let err = Troop::new(monkeys.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "monkey 2 tests divisibility by zero");
+
+    monkeys[2].divisor = 13;
+    monkeys[1].receiver_if_false = 4;
+    let err = Troop::new(monkeys).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "monkey 1 throws to monkey 4, which doesn't exist"
+    );
+
     let input = include_str!("test_files/day_11_test.txt");
-    let monkeys = all_consuming(parse_all_monkeys)(&input).finish().unwrap().1;
+    let corrupt = input.replace("divisible by 19", "divisible by 0");
+    assert!(parse_input(&corrupt).is_err());
+    assert_eq!(
+        troop.monkey_business(20, Relief::DivideByThree),
+        part_1(&troop, Relief::DivideByThree)
+    );
+}
 
-    let result = part_2(&monkeys);
+#[test]
+fn test_summary() {
+    let troop = sample_troop();
+
+    let m = troop.play(20, Relief::DivideByThree).unwrap();
+    let summary = Summary::new(&m, 20);
+
+    assert_eq!(
+        summary.monkey_business,
+        part_1(&troop, Relief::DivideByThree).unwrap()
+    );
+    assert_eq!(summary.monkey_business, 10605);
+    assert_eq!(summary.throws, 101 + 95 + 7 + 105);
+    assert_eq!(
+        summary.to_string(),
+        "Round 20: 308 throws, monkey business 10605"
+    );
+}
 
-    assert_eq!(result, 2713310158);
+#[test]
+fn test_parse_lenient() {
+    let input = include_str!("test_files/day_11_test.txt");
+    let strict = all_consuming(parse_all_monkeys)(input).finish().unwrap().1;
+    let (monkeys, warnings) = parse_monkeys_lenient(input);
+    assert_eq!(monkeys.len(), strict.len());
+    assert!(warnings.is_empty());
+
+    let corrupt = input.replace("new = old * old", "new = old ^ old");
+    assert!(all_consuming(parse_all_monkeys)(&corrupt).finish().is_err());
+
+    let (monkeys, warnings) = parse_monkeys_lenient(&corrupt);
+    assert_eq!(monkeys.len(), 3);
+    assert_eq!(
+        monkeys.iter().map(|m| m.divisor).collect::<Vec<_>>(),
+        vec![23, 19, 17]
+    );
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].block, 2);
+    assert!(warnings[0].to_string().starts_with("skipped block 2: "));
+    // Monkey 0 still throws to monkey 3, which is gone.
+    assert!(Troop::new(monkeys).is_err());
+}
+
+#[test]
+fn test_no_relief() {
+    let input = include_str!("test_files/day_11_test.txt");
+    let troop = sample_troop();
+
+    assert_eq!("none".parse::<Relief>().unwrap(), Relief::None);
+    assert!("half".parse::<Relief>().is_err());
+
+    // Worry reaches 97 bits by round 20, well past what u64 could hold.
+    let wide = troop.widen::<u128>();
+    assert_eq!(part_1(&wide, Relief::None), Ok(10197));
+    assert_eq!(part_1_auto_width(&troop, Relief::None), Ok(10197));
+
+    // Without any division, keeping worry modulo the divisors is exact.
+    let unrelieved = Summary::new(&wide.play(20, Relief::None).unwrap(), 20);
+    let modulo = Summary::new(&wide.play(20, Relief::Modulo).unwrap(), 20);
+    assert_eq!(unrelieved, modulo);
+
+    // Squaring an item enough times still runs out of room.
+    let looping = input.replace(
+        "throw to monkey 3\n\nMonkey 1",
+        "throw to monkey 2\n\nMonkey 1",
+    );
+    let wide = parse_input(&looping).unwrap().widen::<u128>();
+    let err = part_1(&wide, Relief::None).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("worry level overflowed in round "));
+}
+
+#[test]
+fn test_worry_widths() {
+    let narrow = sample_troop();
+    let wide = narrow.widen::<u128>();
+
+    for relief in [Relief::DivideByThree, Relief::Modulo] {
+        assert_eq!(part_1(&narrow, relief), part_1(&wide, relief));
+    }
+    assert_eq!(
+        part_2(&narrow, &mut Budget::default()),
+        part_2(&wide, &mut Budget::default())
+    );
+    assert_eq!(part_2(&wide, &mut Budget::default()), Ok(2713310158));
 }
@@ -1,10 +1,38 @@
-use egui::{Color32, Rect, Rounding, Sense, Slider, Stroke, Vec2};
-use itertools::izip;
+use crate::{
+    answer::Answer,
+    coord,
+    day::{wants_sample, AnswerKind, Day, DayInfo},
+    grid::Bounds,
+    input::significant_lines,
+    palette::Palette,
+    search, state,
+    steppable::{self, RateMeter, Steppable},
+    view::ViewTransform,
+};
+use anyhow::{bail, Context, Result};
+use egui::{Align2, Color32, ColorImage, FontId, Rect, Rounding, Sense, Slider, Stroke, Vec2};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::{self, FilterType},
+    Delay, Frame, RgbaImage,
+};
+use itertools::{izip, Itertools};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::{self, Write},
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
     time::Duration,
 };
 
+const STATE_FILE: &str = "day_12_state.json";
+/// How many pixels wide and tall each tile is in an exported GIF.
+const GIF_SCALE: u32 = 4;
+const GIF_FRAME_MS: u32 = 50;
+
 trait Interpolate {
     type T;
     fn lerp(v: Self::T, f: (Self::T, Self::T), t: (Self::T, Self::T)) -> Self::T;
@@ -42,7 +70,20 @@ impl Interpolate for Color32 {
     }
 }
 
-#[derive(Debug, Copy, Clone, Ord, Eq, PartialEq, PartialOrd)]
+/// Maps elevations from `a` to `z` onto a gradient from `bg` to `fg`.
+fn tile_color(height: usize, bg: Color32, fg: Color32) -> Color32 {
+    let from_bg = Color32::from_gray(Cell::Start.get_height() as u8);
+    let from_fg = Color32::from_gray(Cell::End.get_height() as u8);
+    let tile = Color32::from_gray(height as u8);
+
+    Color32::lerp(tile, (from_bg, from_fg), (bg, fg))
+}
+
+fn svg_color(c: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", c.r(), c.g(), c.b())
+}
+
+#[derive(Debug, Copy, Clone, Ord, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
 enum Cell {
     Start,
     End,
@@ -50,13 +91,21 @@ enum Cell {
 }
 
 impl Cell {
-    fn parse(c: char) -> Option<Self> {
-        match c {
-            'S' => Some(Cell::Start),
-            'E' => Some(Cell::End),
-            'a'..='z' => Some(Cell::Elevation(c as usize)),
-            '\n' => None,
-            _ => panic!("Invalid character"),
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            b'S' => Some(Cell::Start),
+            b'E' => Some(Cell::End),
+            b'a'..=b'z' => Some(Cell::Elevation(b as usize)),
+            _ => None,
+        }
+    }
+
+    /// The inverse of `from_byte`.
+    fn as_char(&self) -> char {
+        match self {
+            Cell::Start => 'S',
+            Cell::End => 'E',
+            Cell::Elevation(h) => *h as u8 as char,
         }
     }
 
@@ -69,12 +118,23 @@ impl Cell {
     }
 }
 
-#[derive(Debug, Copy, Clone, Hash, Ord, Eq, PartialEq, PartialOrd)]
+#[derive(
+    Debug, Default, Copy, Clone, Hash, Ord, Eq, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
 struct Coord {
     x: usize,
     y: usize,
 }
 
+impl From<Coord> for coord::Coord {
+    fn from(value: Coord) -> Self {
+        coord::Coord {
+            x: value.x as i32,
+            y: value.y as i32,
+        }
+    }
+}
+
 impl From<(usize, usize)> for Coord {
     fn from(value: (usize, usize)) -> Self {
         Coord {
@@ -86,162 +146,584 @@ impl From<(usize, usize)> for Coord {
 
 type PrevCell = Option<Coord>;
 
-#[derive(Debug)]
+/// How the search reached a cell: where from, how many steps it took (`g`)
+/// and the Manhattan distance left to the `S` cell (`h`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Visit {
+    prev: PrevCell,
+    g: usize,
+    h: usize,
+}
+
+/// Tiles at least this many points wide have room for their `g` and `h`.
+const SCORE_MIN_SIDE: f32 = 28.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Brush {
+    Off,
+    Raise,
+    Lower,
+}
+
+/// Which cells count as adjacent: the four orthogonal ones, or the
+/// diagonals too.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Connectivity {
+    Four,
+    Eight,
+}
+
+/// Where the search from `E` stops: at `S`, or at the first cell as low
+/// as `S`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchMode {
+    Start,
+    AnyLowest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct Grid {
     width: usize,
     height: usize,
     cells: Vec<Cell>,
-    visited: HashMap<Coord, PrevCell>,
+    /// Loaded from the example in the puzzle text instead of the full input.
+    #[serde(default)]
+    sample: bool,
+    /// JSON can't key a map by `Coord`, so this is rebuilt from
+    /// `visit_order` on load.
+    #[serde(skip)]
+    visited: HashMap<Coord, Visit>,
+    /// Where `S` and `E` are, found once since the brush can't move them.
+    /// Rebuilt on load like `visited`.
+    #[serde(skip)]
+    start: Coord,
+    #[serde(skip)]
+    end: Coord,
+    visit_order: Vec<(Coord, Visit)>,
     current: HashSet<Coord>,
     steps: usize,
     speed: u32,
     paused: bool,
-    step: bool,
+    /// Shows the frame and step rates over the simulation.
+    #[serde(default)]
+    show_rate: bool,
+    #[serde(skip)]
+    rate: RateMeter,
     finished: bool,
+    goal: Option<Coord>,
+    weighted_cost: Option<u32>,
+    show_palette: bool,
+    /// Labels visited tiles with their `g` and `h` when they are big enough.
+    show_scores: bool,
+    #[serde(skip)]
+    palette: Palette,
+    connectivity: Connectivity,
+    mode: SearchMode,
+    brush: Brush,
+    /// Cells already changed by the current brush stroke.
+    painted: HashSet<Coord>,
+}
+
+/// Where the only `cell` in a map `width` cells wide is.
+fn locate(cells: &[Cell], width: usize, cell: Cell) -> Result<Coord> {
+    let mut found = cells
+        .iter()
+        .positions(|&c| c == cell)
+        .map(|i| Coord::from((i % width, i / width)));
+    let at = found
+        .next()
+        .with_context(|| format!("no {} in the map", cell.as_char()))?;
+    if found.next().is_some() {
+        bail!("more than one {} in the map", cell.as_char());
+    }
+    Ok(at)
 }
 
 impl Grid {
-    fn new() -> Self {
-        let i = include_str!("test_files/day_12.txt");
-        Self::parse(i)
+    fn new(palette: Palette) -> Self {
+        Self::with_sample(palette, false)
+    }
+
+    fn with_sample(palette: Palette, sample: bool) -> Self {
+        let i = Day::D12.bundled(sample);
+        Self {
+            palette,
+            sample,
+            ..Self::parse(i).expect("the puzzle input should parse")
+        }
     }
 
-    fn parse(i: &str) -> Self {
-        let width = i.lines().next().expect("Should not be empty").len();
-        let height = i.lines().count();
+    /// Every row must be as wide as the first, and the map must hold exactly
+    /// one `S` and one `E`.
+    fn parse(i: &str) -> Result<Self> {
+        let mut lines = significant_lines(i).peekable();
+        let Some((_, first)) = lines.peek() else {
+            bail!("empty map");
+        };
+        let width = first.len();
+
+        let mut cells = vec![];
+        let mut height = 0;
+        for (n, line) in lines {
+            if line.len() != width {
+                bail!("line {n}: expected {width} cells, found {}", line.len());
+            }
+            for b in line.bytes() {
+                let Some(cell) = Cell::from_byte(b) else {
+                    bail!("line {n}: invalid cell {:?}", b as char);
+                };
+                cells.push(cell);
+            }
+            height += 1;
+        }
+        let start = locate(&cells, width, Cell::Start)?;
+        let end = locate(&cells, width, Cell::End)?;
 
-        Grid {
+        Ok(Grid {
             width,
             height,
-            cells: i
-                .chars()
-                .filter(|c| c.is_alphabetic())
-                .map(|c| Cell::parse(c))
-                .filter_map(|c| match c {
-                    Some(v) => Some(v),
-                    None => None,
-                })
-                .collect(),
+            cells,
+            sample: false,
             visited: Default::default(),
+            start,
+            end,
+            visit_order: vec![],
             current: Default::default(),
             steps: 0,
             speed: 1,
             paused: true,
-            step: false,
+            show_rate: false,
+            rate: RateMeter::default(),
             finished: false,
+            goal: None,
+            weighted_cost: None,
+            show_palette: false,
+            show_scores: false,
+            palette: Palette::default(),
+            connectivity: Connectivity::Four,
+            mode: SearchMode::AnyLowest,
+            brush: Brush::Off,
+            painted: HashSet::new(),
+        })
+    }
+
+    fn save_state(&self, path: &Path) -> Result<()> {
+        state::save(self, path)
+    }
+
+    fn load_state(&mut self, path: &Path) -> Result<()> {
+        let loaded: Self = state::load(path)?;
+        *self = Self {
+            palette: self.palette,
+            visited: loaded.visit_order.iter().copied().collect(),
+            start: locate(&loaded.cells, loaded.width, Cell::Start)?,
+            end: locate(&loaded.cells, loaded.width, Cell::End)?,
+            ..loaded
+        };
+        Ok(())
+    }
+
+    fn bounds(&self) -> Bounds {
+        Bounds {
+            width: self.width,
+            height: self.height,
         }
     }
 
     fn in_bounds(&self, c: Coord) -> bool {
-        c.x < self.width && c.y < self.height
+        self.bounds().contains((c.x, c.y))
     }
 
     fn get_cell(&self, c: Coord) -> Option<&Cell> {
         self.cells.get(c.x + self.width * c.y)
     }
 
-    fn get_end(&self) -> Coord {
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let coord = (x, y).into();
-                if let Cell::End = self.get_cell(coord).unwrap() {
-                    return coord;
-                }
-            }
+    fn set_cell(&mut self, c: Coord, cell: Cell) {
+        if self.in_bounds(c) {
+            self.cells[c.x + self.width * c.y] = cell;
         }
+    }
 
-        #[allow(unreachable_code)]
-        !unreachable!()
+    /// Raises or lowers `c` by one, within `a..=z`. Start and end stay put.
+    fn brush_cell(&mut self, c: Coord) {
+        let Some(&Cell::Elevation(h)) = self.get_cell(c) else {
+            return;
+        };
+        let h = match self.brush {
+            Brush::Off => h,
+            Brush::Raise => (h + 1).min('z' as usize),
+            Brush::Lower => (h - 1).max('a' as usize),
+        };
+        self.set_cell(c, Cell::Elevation(h));
+    }
+
+    /// Forgets the search so far, for after the terrain changes.
+    fn reset_search(&mut self) {
+        self.visited.clear();
+        self.visit_order.clear();
+        self.current.clear();
+        self.steps = 0;
+        self.finished = false;
+        self.goal = None;
+        self.weighted_cost = None;
+    }
+
+    /// The visit record for reaching `c` from `prev`.
+    fn visit(&self, c: Coord, prev: PrevCell) -> Visit {
+        let g = prev.map_or(0, |p| self.visited[&p].g + 1);
+        let h = self.start.x.abs_diff(c.x) + self.start.y.abs_diff(c.y);
+        Visit { prev, g, h }
+    }
+
+    fn is_goal(&self, c: Coord) -> bool {
+        let cell = self.get_cell(c).unwrap();
+        match self.mode {
+            SearchMode::Start => *cell == Cell::Start,
+            SearchMode::AnyLowest => cell.get_height() == Cell::Start.get_height(),
+        }
     }
 
     fn possible_neighbors(&self, c: Coord) -> Vec<Coord> {
         let current_height = self.get_cell(c).unwrap().get_height();
-        let deltas: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-
-        deltas
+        let bounds = self.bounds();
+        let neighbors = match self.connectivity {
+            Connectivity::Four => bounds.neighbors4((c.x, c.y)).collect::<Vec<_>>(),
+            Connectivity::Eight => bounds.neighbors8((c.x, c.y)).collect(),
+        };
+        neighbors
             .into_iter()
-            .filter_map(move |(dx, dy)| {
-                Some(Coord {
-                    x: c.x.checked_add_signed(dx)?,
-                    y: c.y.checked_add_signed(dy)?,
-                })
-                .filter(|&c| self.in_bounds(c))
-                .filter(|&c| self.get_cell(c).unwrap().get_height() >= current_height - 1)
-            })
+            .map(Coord::from)
+            .filter(|&c| self.get_cell(c).unwrap().get_height() >= current_height - 1)
             .collect()
     }
 
+    /// Dijkstra over the same moves as the BFS in `step`, where each move
+    /// costs `cost(from, to)` instead of 1.
+    fn weighted_shortest(&self, cost: impl Fn(&Cell, &Cell) -> u32) -> Option<u32> {
+        let end = self.end;
+        let mut dist = HashMap::from([(end, 0)]);
+        let mut queue = BinaryHeap::from([Reverse((0, end))]);
+
+        while let Some(Reverse((d, curr))) = queue.pop() {
+            let cell = self.get_cell(curr)?;
+            if cell.get_height() == Cell::Start.get_height() {
+                return Some(d);
+            }
+
+            if dist.get(&curr).is_some_and(|&best| d > best) {
+                continue;
+            }
+
+            for neighbor in self.possible_neighbors(curr) {
+                let next = d + cost(cell, self.get_cell(neighbor)?);
+                if dist.get(&neighbor).is_none_or(|&best| next < best) {
+                    dist.insert(neighbor, next);
+                    queue.push(Reverse((next, neighbor)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Every visited cell with the cell it was reached from, in the order the
+    /// search reached them.
+    fn export_visit_order(&self) -> &[(Coord, Visit)] {
+        &self.visit_order
+    }
+
+    /// The shortest path found so far, from the lowest cell reached back to
+    /// the end. Empty until the search has finished.
+    fn path(&self) -> Vec<Coord> {
+        std::iter::successors(self.goal, |c| self.visited.get(c)?.prev).collect()
+    }
+
+    /// One pixel per tile, shaded like `to_svg`, with the path on top.
+    fn render(&self) -> ColorImage {
+        let bg = self.palette.air;
+        let fg = egui::Visuals::dark().text_color();
+        let mut img = ColorImage::new([self.width, self.height], bg);
+        for (pixel, cell) in img.pixels.iter_mut().zip(&self.cells) {
+            *pixel = tile_color(cell.get_height(), bg, fg);
+        }
+        for c in self.path() {
+            img[(c.x, c.y)] = self.palette.arrow;
+        }
+        img
+    }
+
+    /// The search so far, one pixel per tile: shaded like `render`, with
+    /// visited tiles in the palette's visited colour and the frontier and
+    /// any path found drawn over them.
+    fn search_frame(&self) -> RgbaImage {
+        let bg = self.palette.air;
+        let fg = egui::Visuals::dark().text_color();
+        let pixel = |c: Color32| image::Rgba(c.to_array());
+        let mut img = RgbaImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let height = self.cells[y as usize * self.width + x as usize].get_height();
+            pixel(tile_color(height, bg, fg))
+        });
+        for c in self.visited.keys() {
+            img.put_pixel(c.x as u32, c.y as u32, pixel(self.palette.visited));
+        }
+        for c in self.current.iter().copied().chain(self.path()) {
+            img.put_pixel(c.x as u32, c.y as u32, pixel(self.palette.arrow));
+        }
+        img
+    }
+
+    /// The elevation map as SVG, one unit square per cell, with the path
+    /// drawn over it and the start and end of the path marked.
+    fn to_svg(&self) -> String {
+        let bg = self.palette.air;
+        let fg = egui::Visuals::dark().text_color();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\">\n",
+            self.width, self.height
+        );
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let height = self.get_cell((x, y).into()).unwrap().get_height();
+                let fill = svg_color(tile_color(height, bg, fg));
+                writeln!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"1\" height=\"1\" fill=\"{fill}\"/>"
+                )
+                .unwrap();
+            }
+        }
+
+        let path = self.path();
+        let arrow = svg_color(self.palette.arrow);
+        let points = path
+            .iter()
+            .map(|c| format!("{}.5,{}.5", c.x, c.y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            svg,
+            "<polyline points=\"{points}\" fill=\"none\" stroke=\"{arrow}\" stroke-width=\"0.2\"/>"
+        )
+        .unwrap();
+
+        let markers = [(path.first(), 0.4), (path.last(), 0.3)];
+        for (c, r) in markers.into_iter().filter_map(|(c, r)| Some((c?, r))) {
+            writeln!(
+                svg,
+                "<circle cx=\"{}.5\" cy=\"{}.5\" r=\"{r}\" fill=\"{arrow}\"/>",
+                c.x, c.y
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn run_to_end(&mut self) {
+        loop {
+            self.step();
+            if self.finished || self.current.is_empty() {
+                break;
+            }
+        }
+    }
+
     fn step(&mut self) {
         if self.finished {
             return;
         }
 
         if self.current.is_empty() {
-            let end_coord = self.get_end();
+            let end_coord = self.end;
             self.current.insert(end_coord);
-            self.visited.insert(end_coord, PrevCell::from(None));
+            let visit = self.visit(end_coord, None);
+            self.visited.insert(end_coord, visit);
+            self.visit_order.push((end_coord, visit));
             return;
         }
 
         let current = std::mem::take(&mut self.current);
         let mut next = HashSet::new();
-        let mut visited = std::mem::take(&mut self.visited);
 
         for curr in current {
             for neighbor in self.possible_neighbors(curr) {
-                if self.get_cell(neighbor).unwrap().get_height() == Cell::Start.get_height() {
+                if self.is_goal(neighbor) {
+                    let visit = self.visit(neighbor, Some(curr));
+                    self.visited.insert(neighbor, visit);
+                    self.visit_order.push((neighbor, visit));
+                    self.goal = Some(neighbor);
                     self.steps += 1;
                     self.finished = true;
-                    self.visited = visited;
                     return;
                 }
 
-                if visited.contains_key(&neighbor) {
+                if self.visited.contains_key(&neighbor) {
                     continue;
                 }
 
-                visited.insert(neighbor, PrevCell::from(Some(curr)));
+                let visit = self.visit(neighbor, Some(curr));
+                self.visited.insert(neighbor, visit);
+                self.visit_order.push((neighbor, visit));
                 next.insert(neighbor);
             }
         }
 
         self.current = next;
-        self.visited = visited;
         self.steps += 1;
     }
 }
 
+/// Runs the rest of `grid`'s search, writing every `frame_stride`th step
+/// and the last one as a frame of a looping GIF. Returns how many frames
+/// it wrote.
+fn encode_search_gif(grid: &mut Grid, out: impl io::Write, frame_stride: usize) -> Result<usize> {
+    if frame_stride == 0 {
+        bail!("the frame stride has to be at least 1");
+    }
+    let (width, height) = (
+        grid.width as u32 * GIF_SCALE,
+        grid.height as u32 * GIF_SCALE,
+    );
+    let mut encoder = GifEncoder::new(out);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let (mut steps, mut frames) = (0, 0);
+    while !grid.is_done() {
+        grid.step();
+        steps += 1;
+        if steps % frame_stride == 0 || grid.is_done() {
+            let img = imageops::resize(&grid.search_frame(), width, height, FilterType::Nearest);
+            let delay = Delay::from_numer_denom_ms(GIF_FRAME_MS, 1);
+            encoder.encode_frame(Frame::from_parts(img, 0, 0, delay))?;
+            frames += 1;
+        }
+    }
+    Ok(frames)
+}
+
+/// Replays the search into a GIF at `path`, as `encode_search_gif` does.
+fn render_search_gif(grid: &mut Grid, path: &Path, frame_stride: usize) -> Result<usize> {
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    encode_search_gif(grid, BufWriter::new(file), frame_stride)
+}
+
+/// The map in the puzzle's format, including any brush edits.
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.width) {
+            let line = row.iter().map(Cell::as_char).collect::<String>();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Steppable for Grid {
+    fn step(&mut self) {
+        Grid::step(self);
+    }
+
+    /// Done once the start is reached, or once the frontier runs out
+    /// without reaching it.
+    fn is_done(&self) -> bool {
+        self.finished || (!self.visited.is_empty() && self.current.is_empty())
+    }
+
+    /// Reloads the map, undoing any brush strokes, but keeps the settings.
+    fn reset(&mut self) {
+        *self = Self {
+            speed: self.speed,
+            show_rate: self.show_rate,
+            show_palette: self.show_palette,
+            show_scores: self.show_scores,
+            connectivity: self.connectivity,
+            mode: self.mode,
+            brush: self.brush,
+            ..Self::with_sample(self.palette, self.sample)
+        };
+    }
+}
+
 impl eframe::App for Grid {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        steppable::handle_keys(ctx, self);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.button("Reset").clicked() {
-                    *self = Self::new();
+                steppable::controls(ui, self);
+
+                let paused = self.paused;
+                ui.toggle_value(&mut self.paused, if paused { "▶" } else { "⏸" });
+
+                if ui.button("Weighted").clicked() {
+                    self.weighted_cost = self.weighted_shortest(|from, to| {
+                        from.get_height().abs_diff(to.get_height()) as u32
+                    });
+                }
+                if let Some(cost) = self.weighted_cost {
+                    ui.label(format!("Elevation-weighted cost: {cost}"));
+                }
+
+                ui.toggle_value(&mut self.show_palette, "Palette");
+                ui.toggle_value(&mut self.show_scores, "g / h");
+
+                ui.separator();
+                if ui.button("Save").clicked() {
+                    if let Err(e) = self.save_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
+                }
+                if ui.button("Load").clicked() {
+                    if let Err(e) = self.load_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
                 }
 
-                if ui.button("Step").clicked() {
-                    self.step();
+                ui.separator();
+                ui.label("Moves:");
+                let connectivity = self.connectivity;
+                ui.selectable_value(&mut self.connectivity, Connectivity::Four, "4");
+                ui.selectable_value(&mut self.connectivity, Connectivity::Eight, "8");
+                if self.connectivity != connectivity {
+                    self.reset_search();
                 }
 
-                let paused = self.paused;
-                ui.toggle_value(&mut self.paused, if paused { "▶" } else { "⏸" });
+                ui.separator();
+                ui.label("To:");
+                let mode = self.mode;
+                ui.selectable_value(&mut self.mode, SearchMode::Start, "S");
+                ui.selectable_value(&mut self.mode, SearchMode::AnyLowest, "Any a");
+                if self.mode != mode {
+                    self.reset_search();
+                }
+
+                ui.separator();
+                ui.label("Brush:");
+                ui.selectable_value(&mut self.brush, Brush::Off, "Off");
+                ui.selectable_value(&mut self.brush, Brush::Raise, "Raise");
+                ui.selectable_value(&mut self.brush, Brush::Lower, "Lower");
             });
 
             ui.horizontal(|ui| {
                 ui.label("Speed: ");
                 ui.add(Slider::new(&mut self.speed, 1..=20).prefix("x"));
+                ui.toggle_value(&mut self.show_rate, "Rate");
             });
         });
 
-        if self.step {
-            self.step();
-            self.step = false;
-        } else if !self.paused {
-            (0..self.speed).for_each(|_| {
-                self.step();
-            });
+        egui::Window::new("Palette")
+            .open(&mut self.show_palette)
+            .show(ctx, |ui| self.palette.ui(ui));
+
+        let mut steps = 0;
+        if !self.paused {
+            let speed = self.speed;
+            steps = steppable::advance(self, speed);
             ctx.request_repaint_after(Duration::from_millis(25));
         }
+        steppable::rate_overlay(ctx, &mut self.rate, steps, self.show_rate);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             let mut painter_size = ui.available_size_before_wrap();
@@ -262,22 +744,35 @@ impl eframe::App for Grid {
                 - Vec2::new(side * self.width as f32, side * self.height as f32))
                 / 2.;
 
-            let to_panel_pos = |pos: Coord| {
-                ((Vec2::new(pos.x as f32 * side, pos.y as f32 * side)) + anchor).to_pos2()
+            let view = ViewTransform {
+                origin: anchor,
+                zoom: 1.0,
+                cell: Vec2::splat(side),
             };
+            let to_panel_pos = |pos: Coord| view.world_to_screen(pos.into());
 
-            let style = &ctx.style().visuals;
-            painter.rect_filled(res.rect, Rounding::same(0.0), style.window_fill());
+            if self.brush != Brush::Off {
+                if let Some(pos) = res.interact_pointer_pos().filter(|_| res.dragged()) {
+                    let grid_pos = view.screen_to_world(pos);
+                    if let (Ok(x), Ok(y)) = (grid_pos.x.try_into(), grid_pos.y.try_into()) {
+                        let c = Coord { x, y };
+                        if self.in_bounds(c) && self.painted.insert(c) {
+                            self.brush_cell(c);
+                        }
+                    }
+                }
 
-            let to_tile_color = |height: usize| {
-                let bg = style.window_fill();
-                let fg = style.text_color();
-                let from_bg = Color32::from_gray(Cell::Start.get_height() as u8);
-                let from_fg = Color32::from_gray(Cell::End.get_height() as u8);
-                let tile = Color32::from_gray(height as u8);
+                if res.drag_released() && !self.painted.is_empty() {
+                    self.painted.clear();
+                    self.reset_search();
+                }
+            }
 
-                Color32::lerp(tile, (from_bg, from_fg), (bg, fg))
-            };
+            let style = &ctx.style().visuals;
+            painter.rect_filled(res.rect, Rounding::none(), self.palette.air);
+
+            let to_tile_color =
+                |height: usize| tile_color(height, self.palette.air, style.text_color());
 
             for x in 0..self.width {
                 for y in 0..self.height {
@@ -290,14 +785,18 @@ impl eframe::App for Grid {
                 }
             }
 
-            let arrow_color = Color32::YELLOW;
+            let arrow_color = self.palette.arrow;
             for v in self.visited.iter() {
-                match v.1 {
+                match v.1.prev {
                     Some(prev) => {
                         let curr_pos = to_panel_pos(*v.0);
-                        let prev_pos = to_panel_pos(*prev);
+                        let prev_pos = to_panel_pos(prev);
                         painter.circle_filled(curr_pos, side * 0.1, arrow_color);
-                        painter.arrow(prev_pos, curr_pos - prev_pos, Stroke::new(1.0, arrow_color))
+                        painter.arrow(
+                            prev_pos,
+                            curr_pos - prev_pos,
+                            Stroke::new(1.0_f32, arrow_color),
+                        )
                     }
                     None => {
                         let pos = to_panel_pos(*v.0);
@@ -305,12 +804,124 @@ impl eframe::App for Grid {
                     }
                 }
             }
+
+            if self.show_scores && side >= SCORE_MIN_SIDE {
+                let font = FontId::monospace(side * 0.22);
+                for (&c, visit) in &self.visited {
+                    painter.text(
+                        to_panel_pos(c) + Vec2::new(0.0, side * 0.25),
+                        Align2::CENTER_CENTER,
+                        format!("{}/{}", visit.g, visit.h),
+                        font.clone(),
+                        style.text_color(),
+                    );
+                }
+            }
         });
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.palette.save(storage);
+    }
+}
+
+pub(crate) fn app(palette: Palette) -> Box<dyn eframe::App> {
+    Box::new(Grid::new(palette))
+}
+
+/// The finished search over the puzzle input, one pixel per tile.
+pub(crate) fn thumbnail() -> ColorImage {
+    let mut grid = Grid::new(Palette::default());
+    grid.run_to_end();
+    grid.render()
+}
+
+/// The fewest steps between `E` and the cells `mode` is looking for, or
+/// `None` if none can be reached.
+pub fn shortest_steps(grid: &str, mode: SearchMode) -> Result<Option<usize>> {
+    let grid = Grid {
+        mode,
+        ..Grid::parse(grid)?
+    };
+    let found = search::bfs(
+        [grid.end],
+        |&c| grid.possible_neighbors(c),
+        |&c| grid.is_goal(c),
+    );
+    Ok(found.map(|(steps, _)| steps))
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    let steps = shortest_steps(input, SearchMode::Start)?;
+    Ok(steps.context("the start can't reach E")?.into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    let steps = shortest_steps(input, SearchMode::AnyLowest)?;
+    Ok(steps.context("no lowest cell can reach E")?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Hill Climbing Algorithm: searches a height map for the shortest climb",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn main() {
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if let Some(i) = args.iter().position(|arg| arg == "--svg") {
+        let Some(path) = args.get(i + 1) else {
+            bail!("Usage: --svg OUT");
+        };
+        let mut grid = Grid::with_sample(Palette::default(), wants_sample(&args));
+        grid.run_to_end();
+        std::fs::write(path, grid.to_svg())?;
+        return Ok(());
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--gif") {
+        let Some(path) = args.get(i + 1) else {
+            bail!("Usage: --gif OUT [--stride N]");
+        };
+        let stride = match args.iter().position(|arg| arg == "--stride") {
+            Some(i) => args
+                .get(i + 1)
+                .context("--stride needs a number")?
+                .parse()?,
+            None => 1,
+        };
+        let mut grid = Grid::with_sample(Palette::default(), wants_sample(&args));
+        let frames = render_search_gif(&mut grid, Path::new(path), stride)?;
+        println!("Wrote {frames} frames to {path}");
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--bench") {
+        let mut grid = Grid::with_sample(Palette::default(), wants_sample(&args));
+        println!("{}", steppable::bench(&mut grid));
+        if grid.finished {
+            println!("path: {} steps", grid.steps);
+        } else {
+            println!("path: none");
+        }
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--trace") {
+        let mut grid = Grid::with_sample(Palette::default(), wants_sample(&args));
+        grid.run_to_end();
+        for (coord, visit) in grid.export_visit_order() {
+            match visit.prev {
+                Some(prev) => println!("{},{} <- {},{}", coord.x, coord.y, prev.x, prev.y),
+                None => println!("{},{}", coord.x, coord.y),
+            }
+        }
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(1280.0, 720.0)),
         ..Default::default()
@@ -319,7 +930,220 @@ fn main() {
     eframe::run_native(
         "Advent of Code 2022 - Day 12",
         options,
-        Box::new(|_cc| Box::new(Grid::new())),
+        Box::new(move |cc| {
+            let palette = Palette::load(cc.storage);
+            Box::new(Grid::with_sample(palette, wants_sample(&args)))
+        }),
     )
     .expect("eframe failed to start");
+    Ok(())
+}
+
+#[test]
+fn test_weighted_shortest() {
+    let mut grid = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    grid.run_to_end();
+
+    assert_eq!(grid.steps, 29);
+    assert_eq!(grid.weighted_shortest(|_, _| 1), Some(grid.steps as u32));
+}
+
+#[test]
+fn test_visit_order() {
+    let mut grid = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    grid.run_to_end();
+
+    let order = grid.export_visit_order();
+    assert_eq!(order[0].0, grid.end);
+    assert_eq!(order[0].1.prev, None);
+    assert_eq!(order.len(), grid.visited.len());
+}
+
+#[test]
+fn test_svg() {
+    let mut grid = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    grid.run_to_end();
+
+    let svg = grid.to_svg();
+    assert_eq!(svg.matches("<rect ").count(), grid.width * grid.height);
+    assert_eq!(svg.matches("<polyline ").count(), 1);
+
+    let polyline = svg.lines().find(|l| l.starts_with("<polyline")).unwrap();
+    let points = polyline.split('"').nth(1).unwrap();
+    assert_eq!(points.split(' ').count(), grid.steps + 1);
+    assert_eq!(points.split(' ').next_back(), Some("5.5,2.5"));
+}
+
+#[test]
+fn test_search_gif() {
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+
+    let input = include_str!("test_files/day_12_test.txt");
+    let total_steps = steppable::run_to_completion(&mut Grid::parse(input).unwrap());
+    for stride in [1, 3, total_steps, total_steps + 5] {
+        let mut gif = vec![];
+        let mut grid = Grid::parse(input).unwrap();
+        let frames = encode_search_gif(&mut grid, &mut gif, stride).unwrap();
+        assert_eq!(frames, total_steps.div_ceil(stride), "stride {stride}");
+        assert!(grid.finished);
+
+        let decoded = GifDecoder::new(gif.as_slice()).unwrap().into_frames();
+        let decoded = decoded.collect_frames().unwrap();
+        assert_eq!(decoded.len(), frames);
+        let last = decoded.last().unwrap().buffer();
+        assert_eq!(last.dimensions(), (8 * GIF_SCALE, 5 * GIF_SCALE));
+    }
+
+    let mut grid = Grid::parse(input).unwrap();
+    assert!(encode_search_gif(&mut grid, io::sink(), 0).is_err());
+}
+
+#[test]
+fn test_set_cell() {
+    let mut grid = Grid::parse("SbzdefghijklmnopqrstuvwxyE").unwrap();
+    assert_eq!(grid.weighted_shortest(|_, _| 1), None);
+
+    grid.set_cell((2, 0).into(), Cell::Elevation('c' as usize));
+    assert_eq!(grid.weighted_shortest(|_, _| 1), Some(25));
+
+    grid.run_to_end();
+    assert_eq!(grid.steps, 25);
+    grid.brush = Brush::Raise;
+    grid.brush_cell((2, 0).into());
+    grid.reset_search();
+    grid.run_to_end();
+    assert!(!grid.finished);
+}
+
+#[test]
+fn test_connectivity() {
+    // Each cell is as high as its larger coordinate, so the diagonal climbs
+    // one step at a time while going around the edge takes twice as long.
+    let map = (0..26)
+        .map(|y| {
+            (0..26)
+                .map(|x| match (x, y) {
+                    (0, 0) => 'S',
+                    (25, 25) => 'E',
+                    _ => (b'a' + x.max(y) as u8) as char,
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut grid = Grid::parse(&map).unwrap();
+    grid.run_to_end();
+    assert_eq!(grid.steps, 50);
+
+    grid.connectivity = Connectivity::Eight;
+    grid.reset_search();
+    grid.run_to_end();
+    assert_eq!(grid.steps, 25);
+
+    let mut sample = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    sample.connectivity = Connectivity::Eight;
+    sample.run_to_end();
+    assert!(sample.steps < 29);
+}
+
+#[test]
+fn test_steppable() {
+    let mut grid = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    assert!(!grid.is_done());
+    steppable::run_to_completion(&mut grid);
+    assert!(grid.finished);
+    assert_eq!(grid.steps, 29);
+
+    let mut grid = Grid::parse("SbzdefghijklmnopqrstuvwxyE").unwrap();
+    steppable::run_to_completion(&mut grid);
+    assert!(!grid.finished);
+}
+
+#[test]
+fn test_parse_errors() {
+    let ragged = Grid::parse("Sabc\nabc\nabcE").unwrap_err();
+    assert_eq!(ragged.to_string(), "line 2: expected 4 cells, found 3");
+
+    let invalid = Grid::parse("Sab\na1c\nabE").unwrap_err();
+    assert_eq!(invalid.to_string(), "line 2: invalid cell '1'");
+
+    assert!(Grid::parse("").is_err());
+
+    let no_end = Grid::parse("Sab\nabc").unwrap_err();
+    assert_eq!(no_end.to_string(), "no E in the map");
+    let no_start = Grid::parse("abE").unwrap_err();
+    assert_eq!(no_start.to_string(), "no S in the map");
+    let two_ends = Grid::parse("SEa\nabE").unwrap_err();
+    assert_eq!(two_ends.to_string(), "more than one E in the map");
+
+    let grid = Grid::parse("Sab\n\nabE\n").unwrap();
+    assert_eq!((grid.width, grid.height), (3, 2));
+    assert_eq!(grid.cells[0], Cell::Start);
+    assert_eq!(grid.cells[5], Cell::End);
+    assert_eq!(grid.cells[1], Cell::Elevation(b'a' as usize));
+}
+
+#[test]
+fn test_scores() {
+    let mut grid = Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap();
+    grid.run_to_end();
+
+    let mut path = grid.path();
+    path.reverse();
+    let g = path.iter().map(|c| grid.visited[c].g).collect::<Vec<_>>();
+    assert!(g.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(g.first(), Some(&0));
+    assert_eq!(g.last(), Some(&grid.steps));
+
+    assert_eq!(grid.start, Coord { x: 0, y: 0 });
+    let end = grid.end;
+    assert_eq!(grid.visited[&end].h, end.x + end.y);
+    assert!(path.iter().all(|c| grid.visited[c].h == c.x + c.y));
+
+    let mut other = Grid::parse("abE\nSaa").unwrap();
+    assert_eq!((other.start, other.end), ((0, 1).into(), (2, 0).into()));
+    let path = std::env::temp_dir().join(format!("day_12_start_{}.json", std::process::id()));
+    grid.save_state(&path).unwrap();
+    other.load_state(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((other.start, other.end), (grid.start, end));
+}
+
+#[test]
+fn test_render_round_trip() {
+    let input = include_str!("test_files/day_12_test.txt");
+    let grid = Grid::parse(input).unwrap();
+    assert_eq!(grid.to_string(), input);
+    assert_eq!(Grid::parse(&grid.to_string()).unwrap().cells, grid.cells);
+
+    for &b in b"SEaqz" {
+        assert_eq!(Cell::from_byte(b).unwrap().as_char(), b as char);
+    }
+}
+
+#[test]
+fn test_bfs_matches_animation() {
+    for mode in [SearchMode::Start, SearchMode::AnyLowest] {
+        let mut grid = Grid {
+            mode,
+            ..Grid::parse(include_str!("test_files/day_12_test.txt")).unwrap()
+        };
+        let (steps, path) = search::bfs(
+            [grid.end],
+            |&c| grid.possible_neighbors(c),
+            |&c| grid.is_goal(c),
+        )
+        .unwrap();
+        assert_eq!(path.len(), steps + 1);
+        assert_eq!(path[0], grid.end);
+        assert!(grid.is_goal(path[steps]));
+        for pair in path.windows(2) {
+            assert!(grid.possible_neighbors(pair[0]).contains(&pair[1]));
+        }
+
+        grid.run_to_end();
+        assert_eq!(grid.steps, steps);
+        assert_eq!(grid.path().len(), path.len());
+    }
 }
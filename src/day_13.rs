@@ -1,5 +1,12 @@
 use std::fmt;
 
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 
 #[derive(Deserialize, Clone, PartialEq, Eq)]
@@ -20,16 +27,16 @@ impl Node {
 
 impl std::cmp::PartialOrd for Node {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (Node::Num(a), Node::Num(b)) => a.partial_cmp(b),
-            (l, r) => l.with_slice(|l| r.with_slice(|r| l.partial_cmp(r))),
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl std::cmp::Ord for Node {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        match (self, other) {
+            (Node::Num(a), Node::Num(b)) => a.cmp(b),
+            (l, r) => l.with_slice(|l| r.with_slice(|r| l.cmp(r))),
+        }
     }
 }
 
@@ -42,55 +49,106 @@ impl fmt::Debug for Node {
     }
 }
 
-fn part_1(i: &str) -> usize {
+pub(crate) fn part_1(i: &str) -> Result<usize> {
+    let Some(i) = non_empty(i) else {
+        return Ok(0);
+    };
+
+    let lines = i
+        .lines()
+        .enumerate()
+        .map(|(n, line)| (n + 1, line))
+        .collect::<Vec<_>>();
+    let pairs = lines
+        .split(|(_, line)| line.trim().is_empty())
+        .filter(|pair| !pair.is_empty());
     let mut sum = 0;
-    for (i, groups) in i.split("\n\n").enumerate() {
-        let i = i + 1;
-        let mut nodes = groups
-            .lines()
-            .map(|line| serde_json::from_str::<Node>(line).unwrap());
-        let l = nodes.next().unwrap();
-        let r = nodes.next().unwrap();
-
-        if l < r {
-            sum += i;
+    for (index, pair) in pairs.enumerate() {
+        let &[(l_n, l), (r_n, r)] = pair else {
+            bail!("line {}: expected a pair of packets", pair[0].0);
+        };
+
+        if parse_packet(l_n, l)? < parse_packet(r_n, r)? {
+            sum += index + 1;
         }
     }
 
-    sum
+    Ok(sum)
+}
+
+fn parse_packet(n: usize, line: &str) -> Result<Node> {
+    serde_json::from_str(line).with_context(|| format!("line {n}: invalid packet {line:?}"))
+}
+
+fn parse_packets(i: &str) -> Result<Vec<Node>> {
+    significant_lines(i)
+        .map(|(n, line)| parse_packet(n, line))
+        .collect()
 }
 
-fn part_2(i: &str) -> usize {
-    let dividers = vec![
+fn dividers() -> [Node; 2] {
+    [
         Node::List(vec![Node::Num(2)]),
         Node::List(vec![Node::Num(6)]),
-    ];
+    ]
+}
 
-    let mut packets = i
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(|l| serde_json::from_str::<Node>(l).unwrap())
-        .chain(dividers.iter().cloned())
-        .collect::<Vec<_>>();
+/// 1-based positions the two dividers would take if `packets` and the
+/// dividers were sorted together, found by counting what sorts before each
+/// divider instead of sorting. The dividers are counted alongside the
+/// packets, and only strictly smaller ones count, so a packet equal to a
+/// divider (`[[6]]`, or `[6]`) always ranks after it, whatever order the
+/// input lists them in.
+fn divider_ranks(packets: &[Node]) -> (usize, usize) {
+    let dividers = dividers();
+    let rank = |divider: &Node| {
+        1 + packets
+            .iter()
+            .chain(&dividers)
+            .filter(|&packet| packet < divider)
+            .count()
+    };
+    (rank(&dividers[0]), rank(&dividers[1]))
+}
 
-    packets.sort();
+pub(crate) fn part_2(i: &str) -> Result<usize> {
+    let Some(i) = non_empty(i) else {
+        return Ok(0);
+    };
 
-    dividers
-        .iter()
-        .map(|d| packets.binary_search(d).unwrap() + 1)
-        .product::<usize>()
+    let (two, six) = divider_ranks(&parse_packets(i)?);
+    Ok(two * six)
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(input)?.into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(input)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Distress Signal: compares pairs of nested list packets and sorts them",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
 }
 
-fn main() {
-    let input = include_str!("test_files/day_13.txt");
-    println!("Part 1: {}", part_1(input));
-    println!("Part 2: {}", part_2(input));
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    run_day(
+        Part::from_args(&args)?,
+        part_1,
+        part_2,
+        Day::D13.input_for(&args),
+    )
 }
 
 #[test]
 fn test_part_1() {
     let input = include_str!("test_files/day_13_test.txt");
-    let result = part_1(input);
+    let result = part_1(input).unwrap();
 
     assert_eq!(result, 13);
 }
@@ -98,7 +156,78 @@ fn test_part_1() {
 #[test]
 fn test_part_2() {
     let input = include_str!("test_files/day_13_test.txt");
-    let result = part_2(input);
+    let result = part_2(input).unwrap();
 
     assert_eq!(result, 140);
 }
+
+#[test]
+fn test_empty_input() {
+    for input in ["", "\n\n"] {
+        assert_eq!(part_1(input).unwrap(), 0);
+        assert_eq!(part_2(input).unwrap(), 0);
+    }
+}
+
+#[test]
+fn test_parse_errors() {
+    let err = part_1("[1]\n[2]\n\n[1]\n[2,").unwrap_err();
+    assert_eq!(err.to_string(), "line 5: invalid packet \"[2,\"");
+    let err = part_1("[1]\n[2]\n\n[3]\n\n[4]\n[5]").unwrap_err();
+    assert_eq!(err.to_string(), "line 4: expected a pair of packets");
+    assert!(part_2("[1]\nnope")
+        .unwrap_err()
+        .to_string()
+        .contains("line 2"));
+}
+
+#[test]
+fn test_divider_ranks() {
+    let packets = parse_packets(include_str!("test_files/day_13_test.txt")).unwrap();
+    assert_eq!(divider_ranks(&packets), (10, 14));
+
+    let mut sorted = packets.into_iter().chain(dividers()).collect::<Vec<_>>();
+    sorted.sort();
+    let by_sorting = dividers()
+        .iter()
+        .map(|d| sorted.binary_search(d).unwrap() + 1)
+        .product::<usize>();
+    assert_eq!(by_sorting, 140);
+    assert_eq!(
+        part_2(include_str!("test_files/day_13_test.txt")).unwrap(),
+        by_sorting
+    );
+}
+
+#[test]
+fn test_packet_equal_to_divider() {
+    let input = include_str!("test_files/day_13_test.txt");
+    for extra in ["[[2]]", "[2]"] {
+        let with_dup = format!("{extra}\n\n{input}");
+        let packets = parse_packets(&with_dup).unwrap();
+        assert_eq!(divider_ranks(&packets), (10, 15));
+
+        let reversed = packets.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(divider_ranks(&reversed), (10, 15));
+        assert_eq!(part_2(&with_dup).unwrap(), 150);
+    }
+}
+
+#[test]
+fn test_packet_equal_to_six_divider() {
+    let input = include_str!("test_files/day_13_test.txt");
+    // An extra [[2]] moves [[6]] back one place too.
+    for (extra, ranks) in [
+        ("[[6]]", (10, 14)),
+        ("[6]", (10, 14)),
+        ("[[6]]\n[[2]]", (10, 15)),
+    ] {
+        let with_dup = format!("{input}\n{extra}\n");
+        let packets = parse_packets(&with_dup).unwrap();
+        assert_eq!(divider_ranks(&packets), ranks);
+
+        let reversed = packets.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(divider_ranks(&reversed), ranks);
+        assert_eq!(part_2(&with_dup).unwrap(), ranks.0 * ranks.1);
+    }
+}
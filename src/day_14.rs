@@ -1,133 +1,197 @@
-#![feature(extract_if)]
-#![feature(generators)]
-#![feature(iter_from_generator)]
-use std::{fmt, time::Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    path::Path,
+    time::Duration,
+};
 
-use egui::{ColorImage, Slider, TextureOptions};
+use crate::{
+    answer::Answer,
+    coord::{rasterize_segment, Coord},
+    day::{wants_sample, AnswerKind, Day, DayInfo},
+    input::significant_lines,
+    palette::Palette,
+    state,
+    steppable::{self, RateMeter, Steppable},
+    view::ViewTransform,
+};
+use anyhow::{anyhow, bail, Result};
+use egui::{Color32, ColorImage, DragValue, Pos2, Rect, Sense, Slider, TextureOptions, Vec2};
 
 use image::ImageBuffer;
 use nom::{
-    bytes::complete::tag, character::complete as cc, multi::separated_list1, sequence::tuple,
-    Finish, IResult,
+    bytes::complete::tag, character::complete as cc, combinator::all_consuming,
+    multi::separated_list1, sequence::tuple, Finish, IResult,
 };
+use serde::{Deserialize, Serialize};
 
 const SPAWN_POINT: Coord = Coord { x: 500, y: 0 };
+const CURR_COLOR: [u8; 4] = [245, 206, 49, 255];
+const TRACE_COLOR: [u8; 4] = [230, 80, 60, 255];
+const STATE_FILE: &str = "day_14_state.json";
+/// The most cells `Grid::from_input` will allocate.
+const MAX_CELLS: usize = 1 << 26;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 enum Unit {
     Air,
     Rock,
     Sand,
 }
 
-#[derive(Copy, Clone)]
-struct Coord {
-    x: i32,
-    y: i32,
-}
-
 fn parse_coord(i: &str) -> IResult<&str, Coord> {
     let (i, (x, _, y)) = tuple((cc::i32, tag(","), cc::i32))(i)?;
     Ok((i, Coord { x, y }))
 }
 
-impl Coord {
-    fn signum(self) -> Self {
-        Self {
-            x: self.x.signum(),
-            y: self.y.signum(),
-        }
-    }
+struct Line {
+    points: Vec<Coord>,
 }
 
-impl std::ops::Add for Coord {
-    type Output = Coord;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-        }
-    }
+fn parse_line(i: &str) -> IResult<&str, Line> {
+    let (i, points) = separated_list1(tag(" -> "), parse_coord)(i)?;
+    Ok((i, Line { points }))
 }
 
-impl std::ops::AddAssign for Coord {
-    fn add_assign(&mut self, rhs: Self) {
-        self.x += rhs.x;
-        self.y += rhs.y;
+impl Line {
+    fn path_points(&self) -> impl Iterator<Item = Coord> + '_ {
+        let segments = self
+            .points
+            .windows(2)
+            .flat_map(|w| rasterize_segment(w[0], w[1]).skip(1));
+
+        self.points.first().copied().into_iter().chain(segments)
     }
 }
 
-impl std::ops::Sub for Coord {
-    type Output = Coord;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
+/// Where the cave's floor goes, if it has one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FloorMode {
+    /// Sand that falls past the lowest rock is lost.
+    None,
+    /// An endless floor this many rows below the lowest rock.
+    Offset(i32),
 }
 
-impl PartialEq for Coord {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
+impl Default for FloorMode {
+    /// The floor from the puzzle, two rows below the lowest rock.
+    fn default() -> Self {
+        FloorMode::Offset(2)
     }
 }
 
-struct Line {
-    points: Vec<Coord>,
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum RenderMode {
+    Full,
+    SandOnly,
 }
 
-fn parse_line(i: &str) -> IResult<&str, Line> {
-    let (i, points) = separated_list1(tag(" -> "), parse_coord)(i)?;
-    Ok((i, Line { points }))
+/// In `SandOnly` mode rock is drawn as air so only the pile's silhouette
+/// stands out.
+fn unit_color(unit: Unit, mode: RenderMode, palette: &Palette) -> Color32 {
+    match (unit, mode) {
+        (Unit::Air, _) => palette.air,
+        (Unit::Rock, RenderMode::Full) => palette.rock,
+        (Unit::Rock, RenderMode::SandOnly) => palette.air,
+        (Unit::Sand, _) => palette.sand,
+    }
 }
 
-impl Line {
-    fn path_points(&self) -> impl Iterator<Item = Coord> + '_ {
-        std::iter::from_generator(|| {
-            let mut pts = self.points.iter().copied();
-            let Some(mut a) = pts.next() else { return };
-            yield a;
-
-            loop {
-                let Some(b) = pts.next() else { return };
-                let delta = (b - a).signum();
-
-                loop {
-                    a += delta;
-                    yield a;
-                    if a == b {
-                        break;
-                    }
-                }
-            }
-        })
-    }
+/// Statistics about the sand currently in the cave.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct PileStats {
+    settled: usize,
+    /// The mean position of the sand, `None` while there is none.
+    centroid: Option<(f64, f64)>,
+    /// How much of the box around the sand is sand.
+    fill_fraction: f64,
 }
 
+#[derive(Serialize, Deserialize)]
 struct Grid {
     origin: Coord,
     width: usize,
     height: usize,
     data: Vec<Unit>,
+    /// Built from the example in the puzzle text instead of the full input.
+    #[serde(default)]
+    sample: bool,
+    #[serde(default)]
+    floor: FloorMode,
+    /// How many grains the cave holds with its floor, from `fill_count`.
+    /// Rocks only change when the cave is rebuilt, so it is worked out then
+    /// rather than every frame.
+    #[serde(skip)]
+    capacity: Option<usize>,
     grains: Vec<Coord>,
+    trace: Vec<Coord>,
     settled: i32,
+    /// Set once a grain has fallen out of the cave.
+    #[serde(default)]
+    spilled: bool,
+    /// Where each settled grain came to rest, oldest first, so draining can
+    /// take them away in reverse.
+    settle_order: Vec<Coord>,
+    /// Removes sand instead of adding it.
+    draining: bool,
+    spawn_rate: usize,
     speed: u32,
     paused: bool,
-    step: bool,
+    /// Shows the frame and step rates over the simulation.
+    #[serde(default)]
+    show_rate: bool,
+    #[serde(skip)]
+    rate: RateMeter,
+    render_mode: RenderMode,
+    show_palette: bool,
+    #[serde(default)]
+    show_stats: bool,
+    /// The cell last clicked on, shown in the side panel.
+    #[serde(skip)]
+    inspected: Option<Coord>,
+    #[serde(skip)]
+    palette: Palette,
+    /// Redrawn from `data` every frame.
+    #[serde(skip)]
     img: Option<egui::TextureHandle>,
 }
 
 impl Grid {
-    fn new() -> Self {
-        let input = include_str!("test_files/day_14.txt");
+    fn new(palette: Palette) -> Self {
+        Self::with_sample(palette, false)
+    }
 
-        let mut lines = input
-            .lines()
-            .map(|l| parse_line(l).finish().unwrap().1)
-            .collect::<Vec<_>>();
+    fn with_sample(palette: Palette, sample: bool) -> Self {
+        Self::with_floor(palette, sample, FloorMode::default())
+    }
+
+    fn with_floor(palette: Palette, sample: bool, floor: FloorMode) -> Self {
+        Self {
+            palette,
+            sample,
+            ..Self::from_input_with_floor(Day::D14.bundled(sample), floor)
+                .expect("the puzzle input should parse")
+        }
+    }
+
+    /// Parses the rock paths with the default floor.
+    fn from_input(input: &str) -> Result<Self> {
+        Self::from_input_with_floor(input, FloorMode::default())
+    }
+
+    /// The cave always spans x = 300..=700, wider if a rock sticks out or a
+    /// deep floor needs room for the sand pile, and always includes the
+    /// spawn point. An input without rocks gets just the spawn point and the
+    /// floor below it. Without a floor the cave ends at the lowest rock.
+    fn from_input_with_floor(input: &str, floor: FloorMode) -> Result<Self> {
+        let lines = significant_lines(input)
+            .map(|(n, l)| {
+                let parsed = all_consuming(parse_line)(l).finish();
+                parsed
+                    .map(|(_, line)| line)
+                    .map_err(|_| anyhow!("line {n}: invalid rock path {l:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         let (mut min_x, mut min_y, mut max_x, mut max_y) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
 
@@ -142,45 +206,87 @@ impl Grid {
             max_y = max_y.max(point.y);
         }
 
-        let floor_y = max_y + 2;
-        min_x = 300;
-        max_x = 700;
-        max_y = floor_y;
-        lines.push(Line {
-            points: vec![
-                Coord {
-                    x: min_x,
-                    y: floor_y,
-                },
-                Coord {
-                    x: max_x,
-                    y: floor_y,
-                },
-            ],
-        });
+        let floor_y = match floor {
+            FloorMode::None => None,
+            FloorMode::Offset(offset) if offset < 1 => {
+                bail!("floor offset {offset} would put the floor in the rocks")
+            }
+            FloorMode::Offset(offset) => Some(
+                max_y
+                    .checked_add(offset)
+                    .ok_or_else(|| anyhow!("rocks too deep for a floor under them"))?,
+            ),
+        };
+        min_x = min_x.min(300);
+        max_x = max_x.max(700);
+        if let Some(floor_y) = floor_y {
+            // The pile is never wider than twice its height.
+            min_x = min_x.min(SPAWN_POINT.x - floor_y);
+            max_x = max_x.max(SPAWN_POINT.x + floor_y);
+            max_y = floor_y;
+        }
 
         let origin = Coord { x: min_x, y: min_y };
-        let width: usize = (max_x - min_x + 1).try_into().unwrap();
-        let height: usize = (max_y - min_y + 1).try_into().unwrap();
+        let span = |min: i32, max: i32| (i64::from(max) - i64::from(min) + 1) as usize;
+        let (width, height) = (span(min_x, max_x), span(min_y, max_y));
+        if width
+            .checked_mul(height)
+            .is_none_or(|cells| cells > MAX_CELLS)
+        {
+            bail!("a {width}x{height} cave is too big to simulate");
+        }
 
         let mut grid = Self {
             origin,
             width,
             height,
             data: vec![Unit::Air; width * height],
+            sample: false,
+            floor,
+            capacity: None,
             grains: vec![],
+            trace: vec![],
             settled: 0,
+            spilled: false,
+            settle_order: vec![],
+            draining: false,
+            spawn_rate: 1,
             speed: 1,
             paused: true,
-            step: false,
+            show_rate: false,
+            rate: RateMeter::default(),
+            render_mode: RenderMode::Full,
+            show_palette: false,
+            show_stats: false,
+            inspected: None,
+            palette: Palette::default(),
             img: None,
         };
 
         for point in lines.iter().flat_map(|p| p.path_points()) {
             *grid.get_unit_mut(point).unwrap() = Unit::Rock;
         }
+        if let Some(y) = floor_y {
+            grid.set_line(Coord { x: min_x, y }, Coord { x: max_x, y }, Unit::Rock);
+            grid.capacity = Some(grid.fill_count());
+        }
 
-        grid
+        Ok(grid)
+    }
+
+    fn save_state(&self, path: &Path) -> Result<()> {
+        state::save(self, path)
+    }
+
+    fn load_state(&mut self, path: &Path) -> Result<()> {
+        *self = Self {
+            palette: self.palette,
+            ..state::load(path)?
+        };
+        if self.floor != FloorMode::None {
+            self.capacity = Some(self.fill_count());
+        }
+        Ok(())
     }
 
     fn unit_idx(&self, c: Coord) -> Option<usize> {
@@ -203,138 +309,528 @@ impl Grid {
         Some(&self.data[self.unit_idx(c)?])
     }
 
+    /// One pixel per cell, with the last traced path and any grains in
+    /// flight drawn over the units.
+    fn render(&self) -> ColorImage {
+        let mut buff = ImageBuffer::new(self.width as _, self.height as _);
+
+        for (x, y, pixel) in buff.enumerate_pixels_mut() {
+            let coord = Coord {
+                x: x as _,
+                y: y as _,
+            } + self.origin;
+
+            let unit = self.get_unit(coord).unwrap();
+            *pixel = image::Rgba(unit_color(*unit, self.render_mode, &self.palette).to_array());
+        }
+
+        for cell in self.trace.iter().copied() {
+            let Coord { x, y } = cell - self.origin;
+            buff.put_pixel(x as _, y as _, image::Rgba(TRACE_COLOR));
+        }
+
+        for grain in self.grains.iter().copied() {
+            let Coord { x, y } = grain - self.origin;
+            buff.put_pixel(x as _, y as _, image::Rgba(CURR_COLOR));
+        }
+
+        ColorImage::from_rgba_unmultiplied([buff.width() as _, buff.height() as _], &buff)
+    }
+
+    /// Drops grains until one would fall past the lowest rock, or with the
+    /// floor, until the spawn point is buried. A cave built without a floor
+    /// always stops at the first grain that falls out.
+    fn fill(&mut self, has_floor: bool) {
+        let floor = self.origin.y + self.height as i32 - 1;
+        while matches!(self.get_unit(SPAWN_POINT), Some(Unit::Air)) {
+            let rest = *self.trace_grain().last().unwrap();
+            let on_floor = self.floor != FloorMode::None && rest.y == floor - 1;
+            if self.falls_out(rest) || (!has_floor && on_floor) {
+                break;
+            }
+            self.release_traced_grain();
+        }
+    }
+
+    /// Every cell in world coordinates, row by row.
+    fn coords(&self) -> impl Iterator<Item = Coord> {
+        let (origin, width) = (self.origin, self.width as i32);
+        (0..self.height as i32).flat_map(move |y| (0..width).map(move |x| origin + Coord { x, y }))
+    }
+
+    /// Fills every cell on the segment from `a` to `b` with `unit`,
+    /// skipping any that fall outside the grid.
+    fn set_line(&mut self, a: Coord, b: Coord, unit: Unit) {
+        for c in rasterize_segment(a, b) {
+            if let Some(cell) = self.get_unit_mut(c) {
+                *cell = unit;
+            }
+        }
+    }
+
+    /// The image is stretched to fill `rect`, so cells are as wide and tall
+    /// as the rect's size allows on each axis.
+    fn view(&self, rect: Rect) -> ViewTransform {
+        let cell = rect.size() / Vec2::new(self.width as f32, self.height as f32);
+        let origin = Vec2::new(self.origin.x as f32, self.origin.y as f32);
+        ViewTransform {
+            origin: rect.min.to_vec2() + cell / 2.0 - origin * cell,
+            zoom: 1.0,
+            cell,
+        }
+    }
+
+    fn screen_to_cell(&self, pos: Pos2, rect: Rect) -> Option<Coord> {
+        if !rect.contains(pos) {
+            return None;
+        }
+        // The far edges of the rect belong to the last row and column.
+        let max = self.origin
+            + Coord {
+                x: self.width as i32 - 1,
+                y: self.height as i32 - 1,
+            };
+        let Coord { x, y } = self.view(rect).screen_to_world(pos);
+        Some(Coord {
+            x: x.min(max.x),
+            y: y.min(max.y),
+        })
+    }
+
+    fn pile_stats(&self) -> PileStats {
+        let sand = self
+            .coords()
+            .zip(&self.data)
+            .filter(|(_, unit)| matches!(unit, Unit::Sand))
+            .map(|(c, _)| c)
+            .collect::<Vec<_>>();
+        let Some(&first) = sand.first() else {
+            return PileStats {
+                settled: 0,
+                centroid: None,
+                fill_fraction: 0.0,
+            };
+        };
+
+        let (mut min, mut max) = (first, first);
+        let (mut sum_x, mut sum_y) = (0.0, 0.0);
+        for c in &sand {
+            min = Coord {
+                x: min.x.min(c.x),
+                y: min.y.min(c.y),
+            };
+            max = Coord {
+                x: max.x.max(c.x),
+                y: max.y.max(c.y),
+            };
+            sum_x += c.x as f64;
+            sum_y += c.y as f64;
+        }
+        let n = sand.len() as f64;
+        let area = ((max.x - min.x + 1) * (max.y - min.y + 1)) as f64;
+        PileStats {
+            settled: sand.len(),
+            centroid: Some((sum_x / n, sum_y / n)),
+            fill_fraction: n / area,
+        }
+    }
+
+    fn stats_ui(&self, ui: &mut egui::Ui) {
+        let stats = self.pile_stats();
+        ui.heading("Pile");
+        ui.label(format!("Settled: {}", stats.settled));
+        if let Some((x, y)) = stats.centroid {
+            ui.label(format!("Centroid: ({x:.1}, {y:.1})"));
+        }
+        ui.label(format!("Box filled: {:.1}%", stats.fill_fraction * 100.0));
+    }
+
+    /// Picking a different floor starts the cave over.
+    fn floor_ui(&mut self, ui: &mut egui::Ui) {
+        let mut floor = self.floor;
+        ui.label("Floor: ");
+        ui.selectable_value(&mut floor, FloorMode::None, "None");
+        let mut offset = match floor {
+            FloorMode::Offset(offset) => offset,
+            FloorMode::None => 2,
+        };
+        let on = floor != FloorMode::None;
+        let drag = ui.add_enabled(on, DragValue::new(&mut offset).clamp_range(1..=100));
+        if ui.selectable_label(on, "Offset").clicked() || drag.changed() {
+            floor = FloorMode::Offset(offset);
+        }
+
+        if floor != self.floor {
+            self.floor = floor;
+            self.reset();
+        }
+    }
+
+    fn inspect_ui(&self, ui: &mut egui::Ui, cell: Coord) {
+        ui.heading(format!("({}, {})", cell.x, cell.y));
+        let Some(unit) = self.get_unit(cell) else {
+            ui.label("Outside the grid");
+            return;
+        };
+        ui.label(format!("Unit: {unit:?}"));
+        if self.grains.contains(&cell) {
+            ui.label("A grain is falling through here");
+        }
+
+        ui.separator();
+        ui.label("Below:");
+        for (name, below) in ["Down", "Down-left", "Down-right"]
+            .into_iter()
+            .zip(Self::moves(cell))
+        {
+            let state = self
+                .get_unit(below)
+                .map_or("out of bounds".to_string(), |u| format!("{u:?}"));
+            ui.label(format!("{name} ({}, {}): {state}", below.x, below.y));
+        }
+    }
+
+    /// Counts every cell a grain can come to rest in once the floor is in
+    /// place. A grain can reach a cell iff it can reach one of the three
+    /// cells above it, so a flood fill downwards from the spawn point visits
+    /// exactly the cells that end up filled, without simulating each grain.
+    fn fill_count(&self) -> usize {
+        let mut visited = vec![false; self.data.len()];
+        let mut queue = VecDeque::from([SPAWN_POINT]);
+        let mut count = 0;
+
+        while let Some(pos) = queue.pop_front() {
+            let Some(idx) = self.unit_idx(pos) else {
+                continue;
+            };
+            if visited[idx] || matches!(self.data[idx], Unit::Rock) {
+                continue;
+            }
+            visited[idx] = true;
+            count += 1;
+
+            queue.extend([-1, 0, 1].map(|dx| pos + Coord { x: dx, y: 1 }));
+        }
+
+        count
+    }
+
+    /// Straight down, down-left and down-right, in the order a grain tries
+    /// them.
+    fn moves(grain: Coord) -> [Coord; 3] {
+        [0, -1, 1].map(|dx| grain + Coord { x: dx, y: 1 })
+    }
+
+    fn next_position(&self, grain: Coord) -> Option<Coord> {
+        Self::moves(grain)
+            .into_iter()
+            .find(|pos| matches!(self.get_unit(*pos), Some(Unit::Air)))
+    }
+
+    fn falls_out(&self, grain: Coord) -> bool {
+        Self::moves(grain)
+            .into_iter()
+            .any(|pos| self.get_unit(pos).is_none())
+    }
+
+    /// Every cell a single grain passes through from the spawn point until
+    /// it stops moving, ignoring any grains still in flight.
+    fn trace_grain(&self) -> Vec<Coord> {
+        std::iter::successors(Some(SPAWN_POINT), |&grain| self.next_position(grain)).collect()
+    }
+
+    /// Drops one grain all the way down and keeps its path for display.
+    fn release_traced_grain(&mut self) {
+        if !matches!(self.get_unit(SPAWN_POINT), Some(Unit::Air)) {
+            return;
+        }
+
+        let trace = self.trace_grain();
+        let rest = *trace.last().unwrap();
+        if self.falls_out(rest) {
+            self.spilled = true;
+        } else {
+            self.settle(rest);
+        }
+        self.trace = trace;
+    }
+
+    fn settle(&mut self, grain: Coord) {
+        self.settled += 1;
+        self.settle_order.push(grain);
+        *self.get_unit_mut(grain).unwrap() = Unit::Sand;
+    }
+
+    /// Turns the most recently settled grain back into air.
+    fn drain_one(&mut self) {
+        let Some(grain) = self.settle_order.pop() else {
+            return;
+        };
+        self.settled -= 1;
+        *self.get_unit_mut(grain).unwrap() = Unit::Air;
+    }
+
+    /// Moves every grain in flight once and releases up to `spawn_rate` new
+    /// ones. When draining, removes `spawn_rate` grains instead.
     fn step(&mut self) {
+        if self.draining {
+            self.grains.clear();
+            self.trace.clear();
+            (0..self.spawn_rate).for_each(|_| self.drain_one());
+        } else {
+            self.tick(self.spawn_rate);
+        }
+    }
+
+    /// Moves every grain in flight one cell, then releases up to `release`
+    /// new ones as a column below the spawn point, as if they had been
+    /// dropped one after another and fallen straight down.
+    fn tick(&mut self, release: usize) {
         if matches!(self.get_unit(Coord { x: 500, y: 0 }).unwrap(), Unit::Sand) {
             return;
         }
 
+        // In-flight grains aren't in `data`, so each one claims its cell
+        // here. A grain whose way is blocked only by another in flight waits
+        // for it instead of settling, the same as if they fell one at a time.
         let mut grains = std::mem::take(&mut self.grains);
+        let mut reserved = grains.iter().copied().collect::<HashSet<_>>();
         let _ = grains
-            .extract_if(|grain| {
-                let straight_down = *grain + Coord { x: 0, y: 1 };
-                let down_left = *grain + Coord { x: -1, y: 1 };
-                let down_right = *grain + Coord { x: 1, y: 1 };
-                let options = [straight_down, down_left, down_right];
-
-                if let Some(p) = options
-                    .into_iter()
-                    .find(|pos| matches!(self.get_unit(*pos), Some(Unit::Air)))
-                {
-                    *grain = p;
+            .extract_if(.., |grain| {
+                if let Some(p) = self.next_position(*grain) {
+                    if reserved.insert(p) {
+                        reserved.remove(grain);
+                        *grain = p;
+                    }
                     return false;
                 }
 
-                if options.into_iter().any(|pos| self.get_unit(pos).is_none()) {
+                reserved.remove(grain);
+
+                if self.falls_out(*grain) {
+                    self.spilled = true;
                     return true;
                 }
 
-                self.settled += 1;
-                *self.get_unit_mut(*grain).unwrap() = Unit::Sand;
+                self.settle(*grain);
                 true
             })
             .count();
         self.grains = grains;
-        self.grains.push(SPAWN_POINT);
+
+        // Deepest first, so the ones behind it move after it has.
+        let column = (0..release as i32)
+            .map(|y| SPAWN_POINT + Coord { x: 0, y })
+            .take_while(|&c| matches!(self.get_unit(c), Some(Unit::Air)) && !reserved.contains(&c))
+            .collect::<Vec<_>>();
+        self.grains.extend(column.into_iter().rev());
     }
 }
 
 impl fmt::Debug for Grid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let coord = Coord {
-                    x: x as _,
-                    y: y as _,
-                } + self.origin;
-                let unit = self.get_unit(coord).unwrap();
-                let u = match unit {
-                    Unit::Air => '.',
-                    Unit::Rock => '#',
-                    Unit::Sand => 'o',
-                };
-                write!(f, "{u}")?;
+        for c in self.coords() {
+            let u = match self.get_unit(c).unwrap() {
+                Unit::Air => '.',
+                Unit::Rock => '#',
+                Unit::Sand => 'o',
+            };
+            write!(f, "{u}")?;
+            if c.x - self.origin.x + 1 == self.width as i32 {
+                writeln!(f)?;
             }
-            writeln!(f)?;
         }
         Ok(())
     }
 }
 
+impl Steppable for Grid {
+    fn step(&mut self) {
+        Grid::step(self);
+    }
+
+    /// Done once sand has piled up to the spawn point or started falling out
+    /// of the cave, or when draining, once it is all gone.
+    fn is_done(&self) -> bool {
+        if self.draining {
+            return self.settle_order.is_empty();
+        }
+        self.spilled || matches!(self.get_unit(SPAWN_POINT), Some(Unit::Sand))
+    }
+
+    /// Empties the cave but keeps the settings.
+    fn reset(&mut self) {
+        *self = Self {
+            draining: self.draining,
+            spawn_rate: self.spawn_rate,
+            speed: self.speed,
+            show_rate: self.show_rate,
+            render_mode: self.render_mode,
+            show_palette: self.show_palette,
+            show_stats: self.show_stats,
+            ..Self::with_floor(self.palette, self.sample, self.floor)
+        };
+    }
+}
+
 impl eframe::App for Grid {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        steppable::handle_keys(ctx, self);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if ui.button("Reset").clicked() {
-                    *self = Self::new();
-                }
-
-                if ui.button("Step").clicked() {
-                    self.step();
-                }
+                steppable::controls(ui, self);
 
                 let paused = self.paused;
                 ui.toggle_value(&mut self.paused, if paused { "▶" } else { "⏸" });
+
+                ui.separator();
+                ui.selectable_value(&mut self.draining, false, "Fill");
+                ui.selectable_value(&mut self.draining, true, "Drain");
+
+                ui.separator();
+                match self.capacity {
+                    Some(capacity) => ui.label(format!("Settled: {} / {capacity}", self.settled)),
+                    None => ui.label(format!("Settled: {}", self.settled)),
+                };
+
+                ui.separator();
+                ui.selectable_value(&mut self.render_mode, RenderMode::Full, "Rock + sand");
+                ui.selectable_value(&mut self.render_mode, RenderMode::SandOnly, "Sand only");
+
+                ui.separator();
+                ui.toggle_value(&mut self.show_palette, "Palette");
+                ui.toggle_value(&mut self.show_stats, "Stats");
+
+                ui.separator();
+                if ui.button("Save").clicked() {
+                    if let Err(e) = self.save_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
+                }
+                if ui.button("Load").clicked() {
+                    if let Err(e) = self.load_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
+                }
             });
 
             ui.horizontal(|ui| {
                 ui.label("Speed: ");
                 ui.add(Slider::new(&mut self.speed, 1..=20).prefix("x"));
+                ui.toggle_value(&mut self.show_rate, "Rate");
+
+                ui.separator();
+                ui.label("Spawn rate: ");
+                ui.add(Slider::new(&mut self.spawn_rate, 1..=10).suffix(" / step"));
+
+                ui.separator();
+                self.floor_ui(ui);
             });
         });
 
-        if self.step {
-            self.step();
-            self.step = false;
-        } else if !self.paused {
-            (0..self.speed).for_each(|_| {
-                self.step();
+        egui::Window::new("Palette")
+            .open(&mut self.show_palette)
+            .show(ctx, |ui| self.palette.ui(ui));
+
+        if self.show_stats {
+            egui::SidePanel::left("stats_panel").show(ctx, |ui| self.stats_ui(ui));
+        }
+
+        if let Some(cell) = self.inspected {
+            egui::SidePanel::right("inspect_panel").show(ctx, |ui| {
+                self.inspect_ui(ui, cell);
+                if ui.button("Close").clicked() {
+                    self.inspected = None;
+                }
             });
+        }
+
+        let mut steps = 0;
+        if !self.paused {
+            let speed = self.speed;
+            steps = steppable::advance(self, speed);
             ctx.request_repaint_after(Duration::from_millis(25));
         }
+        steppable::rate_overlay(ctx, &mut self.rate, steps, self.show_rate);
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            let mut buff = ImageBuffer::new(self.width as _, self.height as _);
-
-            let style = &ctx.style().visuals;
-            let air_color: [u8; 4] = style.window_fill().to_array();
-            let rock_color: [u8; 4] = [160, 160, 160, 255];
-            let sand_color: [u8; 4] = [130, 127, 88, 255];
-            let curr_color: [u8; 4] = [245, 206, 49, 255];
-
-            for (x, y, pixel) in buff.enumerate_pixels_mut() {
-                let coord = Coord {
-                    x: x as _,
-                    y: y as _,
-                } + self.origin;
-
-                let unit = self.get_unit(coord).unwrap();
-                let color = match unit {
-                    Unit::Air => &air_color,
-                    Unit::Rock => &rock_color,
-                    Unit::Sand => &sand_color,
-                };
+            let img = self.render();
+            self.img = Some(ui.ctx().load_texture("", img, TextureOptions::NEAREST));
 
-                *pixel = image::Rgba(*color);
-            }
+            let Some(img) = self.img.as_ref() else {
+                return;
+            };
+            let res = ui
+                .image(img, ui.available_size())
+                .interact(Sense::click())
+                .on_hover_text("Click a cell to inspect it, or the spawn point to trace one grain");
 
-            for grain in self.grains.iter().copied() {
-                let Coord { x, y } = grain - self.origin;
-                buff.put_pixel(x as _, y as _, image::Rgba(curr_color));
+            let clicked = res.interact_pointer_pos().filter(|_| res.clicked());
+            if let Some(cell) = clicked.and_then(|pos| self.screen_to_cell(pos, res.rect)) {
+                self.inspected = Some(cell);
+                let offset = cell - SPAWN_POINT;
+                if offset.x.abs() <= 3 && offset.y.abs() <= 3 {
+                    self.release_traced_grain();
+                }
             }
+        });
+    }
 
-            let img =
-                ColorImage::from_rgba_unmultiplied([buff.width() as _, buff.height() as _], &buff);
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.palette.save(storage);
+    }
+}
 
-            self.img = Some(ui.ctx().load_texture("", img, TextureOptions::NEAREST));
+pub(crate) fn app(palette: Palette) -> Box<dyn eframe::App> {
+    Box::new(Grid::new(palette))
+}
 
-            if let Some(img) = self.img.as_ref() {
-                ui.image(img, ui.available_size());
-            }
-        });
+/// The puzzle input filled with sand down to the floor, one pixel per
+/// cell.
+pub(crate) fn thumbnail() -> ColorImage {
+    let mut grid = Grid::new(Palette::default());
+    grid.fill(true);
+    grid.trace.clear();
+    grid.render()
+}
+
+/// How many grains settle before one would fall past the lowest rock, or
+/// with the floor, before the spawn point is buried. The floored count comes
+/// from the flood fill; only the count without one simulates each grain.
+pub fn settled_count(grid: &str, has_floor: bool) -> Result<usize> {
+    let mut grid = Grid::from_input(grid)?;
+    if has_floor {
+        return Ok(grid.fill_count());
+    }
+    grid.fill(false);
+    Ok(grid.settled as usize)
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(settled_count(input, false)?.into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(settled_count(input, true)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Regolith Reservoir: pours sand into a cave of rock paths until it settles",
+        answers: [AnswerKind::Int, AnswerKind::Int],
     }
 }
 
-fn main() -> Result<(), eframe::Error> {
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.iter().any(|arg| arg == "--bench") {
+        let mut grid = Grid::with_sample(Palette::default(), wants_sample(&args));
+        println!("{}", steppable::bench(&mut grid));
+        println!("settled: {}", grid.settled);
+        return Ok(());
+    }
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(1280.0, 720.0)),
         ..Default::default()
@@ -343,6 +839,312 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Advent of Code 2022 - Day 14",
         options,
-        Box::new(|_cc| Box::new(Grid::new())),
+        Box::new(move |cc| {
+            let palette = Palette::load(cc.storage);
+            Box::new(Grid::with_sample(palette, wants_sample(&args)))
+        }),
     )
+    .expect("eframe failed to start");
+    Ok(())
+}
+
+#[test]
+fn test_unit_color() {
+    let palette = Palette::dark();
+
+    assert_eq!(
+        unit_color(Unit::Air, RenderMode::Full, &palette),
+        palette.air
+    );
+    assert_eq!(
+        unit_color(Unit::Rock, RenderMode::Full, &palette),
+        palette.rock
+    );
+    assert_eq!(
+        unit_color(Unit::Sand, RenderMode::Full, &palette),
+        palette.sand
+    );
+
+    assert_eq!(
+        unit_color(Unit::Air, RenderMode::SandOnly, &palette),
+        palette.air
+    );
+    assert_eq!(
+        unit_color(Unit::Rock, RenderMode::SandOnly, &palette),
+        palette.air
+    );
+    assert_eq!(
+        unit_color(Unit::Sand, RenderMode::SandOnly, &palette),
+        palette.sand
+    );
+}
+
+#[test]
+fn test_fill_count() {
+    let input = include_str!("test_files/day_14_test.txt");
+    let mut simulated = Grid::from_input(input).unwrap();
+    simulated.fill(true);
+    assert_eq!(simulated.settled, 93);
+    assert_eq!(settled_count(input, true).unwrap(), 93);
+
+    let mut grid = Grid::from_input(input).unwrap();
+    let expected = grid.fill_count();
+    assert_eq!(grid.capacity, Some(expected));
+
+    while !matches!(grid.get_unit(SPAWN_POINT), Some(Unit::Sand)) {
+        grid.step();
+    }
+
+    assert_eq!(grid.settled, 93);
+    assert_eq!(expected, 93);
+    assert_eq!(grid.capacity, Some(93));
+
+    let path = std::env::temp_dir().join(format!("day_14_capacity_{}.json", std::process::id()));
+    grid.save_state(&path).unwrap();
+    let mut loaded = Grid::from_input_with_floor("", FloorMode::None).unwrap();
+    assert_eq!(loaded.capacity, None);
+    loaded.load_state(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.capacity, Some(93));
+}
+
+#[test]
+fn test_trace_grain() {
+    let grid = Grid::from_input("495,5 -> 505,5").unwrap();
+    let trace = grid.trace_grain();
+
+    assert_eq!(trace.first(), Some(&SPAWN_POINT));
+    assert_eq!(trace.last(), Some(&Coord { x: 500, y: 4 }));
+    assert!(trace.iter().all(|c| c.x == 500));
+    assert_eq!(trace.len(), 5);
+
+    let mut grid = grid;
+    grid.release_traced_grain();
+    assert_eq!(grid.settled, 1);
+    assert_eq!(grid.trace_grain().last(), Some(&Coord { x: 499, y: 4 }));
+}
+
+#[test]
+fn test_spawn_rate() {
+    let fill = |spawn_rate| {
+        let mut grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+        grid.spawn_rate = spawn_rate;
+        while !matches!(grid.get_unit(SPAWN_POINT), Some(Unit::Sand)) {
+            grid.step();
+            let mut grains = grid.grains.clone();
+            grains.sort_by_key(|c| (c.x, c.y));
+            grains.dedup();
+            assert_eq!(grains.len(), grid.grains.len());
+        }
+        (grid.settled, format!("{grid:?}"))
+    };
+
+    let single = fill(1);
+    assert_eq!(single.0, 93);
+    assert_eq!(fill(5), single);
+}
+
+#[test]
+fn test_spawn_rate_releases_together() {
+    let in_flight = |spawn_rate| {
+        let mut grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+        grid.spawn_rate = spawn_rate;
+        grid.step();
+        grid.grains
+    };
+
+    assert_eq!(in_flight(1), [SPAWN_POINT]);
+    let four = in_flight(4);
+    assert_eq!(four.len(), 4);
+    assert_eq!(four[0], Coord { x: 500, y: 3 });
+    assert_eq!(four[3], SPAWN_POINT);
+}
+
+#[test]
+fn test_grains_in_flight() {
+    // A one-wide channel five cells deep, with grains released from the top
+    // first so each moves before the one below it gets out of the way.
+    let input = "499,0 -> 499,5 -> 501,5 -> 501,0";
+    let mut grid = Grid::from_input(input).unwrap();
+    grid.grains = (0..4).map(|y| Coord { x: 500, y }).collect();
+    while !grid.is_done() {
+        grid.tick(1);
+        let mut grains = grid.grains.clone();
+        grains.sort_by_key(|c| (c.x, c.y));
+        grains.dedup();
+        assert_eq!(grains.len(), grid.grains.len());
+        for &grain in &grid.grains {
+            assert!(matches!(grid.get_unit(grain), Some(Unit::Air)));
+        }
+    }
+    assert_eq!(grid.settled, 5);
+    assert!((0..5).all(|y| matches!(grid.get_unit(Coord { x: 500, y }), Some(Unit::Sand))));
+}
+
+#[test]
+fn test_steppable() {
+    let mut grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+    assert!(!grid.is_done());
+    steppable::run_to_completion(&mut grid);
+    assert_eq!(grid.settled, 93);
+}
+
+#[test]
+fn test_screen_to_cell() {
+    let grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+    assert_eq!(grid.origin, Coord { x: 300, y: 0 });
+
+    // Every cell drawn 2px wide and 3px tall, offset from the window corner.
+    let rect = Rect::from_min_size(
+        Pos2::new(10.0, 20.0),
+        egui::vec2(grid.width as f32 * 2.0, grid.height as f32 * 3.0),
+    );
+    let at = |x: f32, y: f32| grid.screen_to_cell(Pos2::new(x, y), rect);
+
+    assert_eq!(at(10.0, 20.0), Some(grid.origin));
+    assert_eq!(at(10.0 + 200.0 * 2.0 + 1.0, 21.0), Some(SPAWN_POINT));
+    assert_eq!(
+        at(10.0 + 201.0 * 2.0 + 1.5, 20.0 + 9.0 * 3.0 + 2.5),
+        Some(Coord { x: 501, y: 9 })
+    );
+    assert_eq!(
+        at(rect.max.x, rect.max.y),
+        Some(
+            grid.origin
+                + Coord {
+                    x: grid.width as i32 - 1,
+                    y: grid.height as i32 - 1,
+                }
+        )
+    );
+    assert_eq!(at(9.0, 20.0), None);
+    assert_eq!(at(10.0, rect.max.y + 1.0), None);
+}
+
+#[test]
+fn test_drain() {
+    let mut grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+    let initial = grid.data.clone();
+    grid.spawn_rate = 3;
+    steppable::run_to_completion(&mut grid);
+    assert_eq!(grid.settled, 93);
+    assert_eq!(grid.settle_order.len(), 93);
+
+    grid.draining = true;
+    assert!(!grid.is_done());
+    grid.step();
+    assert_eq!(grid.settled, 90);
+    // The last grain to settle is the one on the spawn point.
+    assert_eq!(grid.get_unit(SPAWN_POINT), Some(&Unit::Air));
+
+    steppable::run_to_completion(&mut grid);
+    assert_eq!(grid.settled, 0);
+    assert!(grid.grains.is_empty());
+    assert_eq!(grid.data, initial);
+}
+
+#[test]
+fn test_set_line() {
+    let mut grid = Grid::from_input("495,9 -> 505,9").unwrap();
+    let before = grid
+        .coords()
+        .filter(|&c| grid.get_unit(c) == Some(&Unit::Rock))
+        .count();
+    let (a, b) = (Coord { x: 498, y: 1 }, Coord { x: 502, y: 5 });
+    grid.set_line(a, b, Unit::Sand);
+
+    let sand = grid
+        .coords()
+        .filter(|&c| grid.get_unit(c) == Some(&Unit::Sand))
+        .collect::<Vec<_>>();
+    let expected = (0..5).map(|i| a + Coord { x: i, y: i }).collect::<Vec<_>>();
+    assert_eq!(sand, expected);
+    assert!(grid
+        .coords()
+        .filter(|c| !expected.contains(c))
+        .all(|c| grid.get_unit(c) != Some(&Unit::Sand)));
+    assert_eq!(
+        grid.coords()
+            .filter(|&c| grid.get_unit(c) == Some(&Unit::Rock))
+            .count(),
+        before
+    );
+
+    grid.set_line(Coord { x: 0, y: 0 }, Coord { x: 10, y: 0 }, Unit::Rock);
+    assert_eq!(grid.coords().count(), grid.width * grid.height);
+}
+
+#[test]
+fn test_no_rocks() {
+    for input in ["", "\n# no rocks\n"] {
+        let grid = Grid::from_input(input).unwrap();
+        assert_eq!(grid.origin, Coord { x: 300, y: 0 });
+        assert_eq!((grid.width, grid.height), (401, 3));
+        assert_eq!(grid.get_unit(SPAWN_POINT), Some(&Unit::Air));
+        assert_eq!(grid.get_unit(Coord { x: 500, y: 2 }), Some(&Unit::Rock));
+    }
+    assert_eq!(settled_count("", true).unwrap(), 4);
+    assert_eq!(settled_count("", false).unwrap(), 0);
+
+    let wide = Grid::from_input("250,3 -> 250,5").unwrap();
+    assert_eq!(wide.origin, Coord { x: 250, y: 0 });
+    assert_eq!(wide.get_unit(Coord { x: 250, y: 4 }), Some(&Unit::Rock));
+
+    let err = Grid::from_input("498,4 -> 498,6\n1 -> 2").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: invalid rock path \"1 -> 2\"");
+    assert!(Grid::from_input("0,0 -> 0,2000000000").is_err());
+}
+
+#[test]
+fn test_pile_stats() {
+    let mut grid = Grid::from_input(include_str!("test_files/day_14_test.txt")).unwrap();
+    assert_eq!(grid.pile_stats().centroid, None);
+
+    grid.fill(true);
+    let stats = grid.pile_stats();
+    assert_eq!(stats.settled, 93);
+    let (x, y) = stats.centroid.unwrap();
+    assert!((495.0..=505.0).contains(&x), "{x}");
+    assert!(y > SPAWN_POINT.y as f64 && y < 11.0, "{y}");
+    assert!(stats.fill_fraction > 0.0 && stats.fill_fraction <= 1.0);
+}
+
+#[test]
+fn test_floor_mode() {
+    let input = include_str!("test_files/day_14_test.txt");
+    let fill = |floor| {
+        let mut grid = Grid::from_input_with_floor(input, floor).unwrap();
+        grid.fill(true);
+        grid
+    };
+
+    let default = fill(FloorMode::default());
+    assert_eq!(default.floor, FloorMode::Offset(2));
+    assert_eq!(default.settled, 93);
+    let deeper = fill(FloorMode::Offset(5));
+    assert_eq!(deeper.height, default.height + 3);
+    assert!(deeper.settled > default.settled, "{}", deeper.settled);
+    assert_eq!(deeper.settled as usize, deeper.fill_count());
+
+    // Without a floor, sand stops at the first grain that falls out.
+    assert_eq!(fill(FloorMode::None).settled, 24);
+    let mut grid = Grid::from_input_with_floor(input, FloorMode::None).unwrap();
+    grid.spawn_rate = 3;
+    steppable::run_to_completion(&mut grid);
+    assert_eq!(grid.settled, 24);
+
+    assert!(Grid::from_input_with_floor(input, FloorMode::Offset(0)).is_err());
+}
+
+#[test]
+fn test_bench() {
+    let mut grid = Grid::with_sample(Palette::default(), true);
+    let report = steppable::bench(&mut grid);
+    assert!(grid.is_done());
+    assert_eq!(grid.settled, 93);
+    assert!(report.steps > 0);
+    assert!(report.steps_per_second() > 0.0);
+    assert!(report
+        .to_string()
+        .starts_with(&format!("{} steps in ", report.steps)));
 }
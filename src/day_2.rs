@@ -1,97 +1,135 @@
-#[derive(Clone, Copy)]
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{bail, Context, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Choice {
     Rock = 1,
     Paper = 2,
     Scissors = 3,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Outcome {
     Win = 6,
     Tie = 3,
     Loss = 0,
 }
 
-fn get_match_outcome(opponent: Choice, player: Choice) -> Outcome {
-    match (opponent, player) {
-        (Choice::Rock, Choice::Rock) => Outcome::Tie,
-        (Choice::Rock, Choice::Paper) => Outcome::Win,
-        (Choice::Rock, Choice::Scissors) => Outcome::Loss,
-        (Choice::Paper, Choice::Rock) => Outcome::Loss,
-        (Choice::Paper, Choice::Paper) => Outcome::Tie,
-        (Choice::Paper, Choice::Scissors) => Outcome::Win,
-        (Choice::Scissors, Choice::Rock) => Outcome::Win,
-        (Choice::Scissors, Choice::Paper) => Outcome::Loss,
-        (Choice::Scissors, Choice::Scissors) => Outcome::Tie,
+/// Which choice beats which, `(a, b)` meaning `a` beats `b`. A variant with
+/// more gestures only needs new `Choice`s and rows here.
+type Beats = [(Choice, Choice)];
+
+const STANDARD: &Beats = &[
+    (Choice::Rock, Choice::Scissors),
+    (Choice::Paper, Choice::Rock),
+    (Choice::Scissors, Choice::Paper),
+];
+
+fn get_match_outcome(beats: &Beats, opponent: Choice, player: Choice) -> Outcome {
+    if opponent == player {
+        Outcome::Tie
+    } else if beats.contains(&(player, opponent)) {
+        Outcome::Win
+    } else {
+        Outcome::Loss
     }
 }
 
-fn get_choice_from_outcome(opponent: Choice, outcome: Outcome) -> Choice {
-    match (opponent, outcome) {
-        (Choice::Rock, Outcome::Win) => Choice::Paper,
-        (Choice::Rock, Outcome::Tie) => Choice::Rock,
-        (Choice::Rock, Outcome::Loss) => Choice::Scissors,
-        (Choice::Paper, Outcome::Win) => Choice::Scissors,
-        (Choice::Paper, Outcome::Tie) => Choice::Paper,
-        (Choice::Paper, Outcome::Loss) => Choice::Rock,
-        (Choice::Scissors, Outcome::Win) => Choice::Rock,
-        (Choice::Scissors, Outcome::Tie) => Choice::Scissors,
-        (Choice::Scissors, Outcome::Loss) => Choice::Paper,
+fn get_choice_from_outcome(beats: &Beats, opponent: Choice, outcome: Outcome) -> Choice {
+    let choice = match outcome {
+        Outcome::Tie => return opponent,
+        Outcome::Win => beats.iter().find(|(_, loser)| *loser == opponent),
+        Outcome::Loss => beats.iter().find(|(winner, _)| *winner == opponent),
+    };
+    match (choice, outcome) {
+        (Some(&(winner, _)), Outcome::Win) => winner,
+        (Some(&(_, loser)), _) => loser,
+        (None, _) => panic!("nothing in the table gives a {outcome:?} against {opponent:?}"),
     }
 }
 
 fn get_match_score(opponent: Choice, player: Choice) -> u32 {
-    player as u32 + get_match_outcome(opponent, player) as u32
+    player as u32 + get_match_outcome(STANDARD, opponent, player) as u32
 }
 
-fn letter_to_choice(letter: char) -> Choice {
+fn letter_to_choice(letter: u8) -> Option<Choice> {
     match letter {
-        'A' | 'X' => Choice::Rock,
-        'B' | 'Y' => Choice::Paper,
-        'C' | 'Z' => Choice::Scissors,
-        _ => panic!(),
+        b'A' | b'X' => Some(Choice::Rock),
+        b'B' | b'Y' => Some(Choice::Paper),
+        b'C' | b'Z' => Some(Choice::Scissors),
+        _ => None,
     }
 }
 
-fn letter_to_outcome(letter: char) -> Outcome {
+fn letter_to_outcome(letter: u8) -> Option<Outcome> {
     match letter {
-        'X' => Outcome::Loss,
-        'Y' => Outcome::Tie,
-        'Z' => Outcome::Win,
-        _ => panic!(),
+        b'X' => Some(Outcome::Loss),
+        b'Y' => Some(Outcome::Tie),
+        b'Z' => Some(Outcome::Win),
+        _ => None,
     }
 }
 
-fn part_1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|line| {
-            get_match_score(
-                letter_to_choice(line.as_bytes()[0] as char),
-                letter_to_choice(line.as_bytes()[2] as char),
-            )
-        })
-        .sum::<u32>()
-}
-
-fn part_2(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|line| {
-            get_match_score(
-                letter_to_choice(line.as_bytes()[0] as char),
-                get_choice_from_outcome(
-                    letter_to_choice(line.as_bytes()[0] as char),
-                    letter_to_outcome(line.as_bytes()[2] as char),
-                ),
-            )
+/// Sums `score` over every round, given the opponent's choice and the
+/// second letter of the round.
+fn total_score(input: &str, score: impl Fn(Choice, u8) -> Option<u32>) -> Result<u32> {
+    let Some(input) = non_empty(input) else {
+        return Ok(0);
+    };
+
+    significant_lines(input)
+        .map(|(n, line)| {
+            let &[opponent, b' ', second] = line.as_bytes() else {
+                bail!("line {n}: expected a round like \"A Y\", found {line:?}");
+            };
+            letter_to_choice(opponent)
+                .and_then(|opponent| score(opponent, second))
+                .with_context(|| format!("line {n}: invalid round {line:?}"))
         })
-        .sum::<u32>()
+        .sum()
+}
+
+pub(crate) fn part_1(input: &str) -> Result<u32> {
+    total_score(input, |opponent, player| {
+        Some(get_match_score(opponent, letter_to_choice(player)?))
+    })
+}
+
+pub(crate) fn part_2(input: &str) -> Result<u32> {
+    total_score(input, |opponent, outcome| {
+        let player = get_choice_from_outcome(STANDARD, opponent, letter_to_outcome(outcome)?);
+        Some(get_match_score(opponent, player))
+    })
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(input)?.into())
 }
 
-fn main() {
-    let input = include_str!("test_files/day_2.txt");
-    println!("Part 1: {}", part_1(&input));
-    println!("Part 2: {}", part_2(&input));
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(input)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Rock Paper Scissors: scores a strategy guide read as moves, then as outcomes",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    run_day(
+        Part::from_args(&args)?,
+        part_1,
+        part_2,
+        Day::D2.input_for(&args),
+    )
 }
 
 #[test]
@@ -100,7 +138,16 @@ fn part_1_test() {
 B X
 C Z";
 
-    assert_eq!(part_1(input), 15);
+    assert_eq!(part_1(input).unwrap(), 15);
+    assert_eq!(part_1("").unwrap(), 0);
+
+    let err = part_1("A Y\nB W").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: invalid round \"B W\"");
+    let err = part_1("A Y\n\nAY").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 3: expected a round like \"A Y\", found \"AY\""
+    );
 }
 
 #[test]
@@ -109,5 +156,28 @@ fn part_2_test() {
 B X
 C Z";
 
-    assert_eq!(part_2(input), 12);
+    assert_eq!(part_2(input).unwrap(), 12);
+    assert!(part_2("A A").is_err());
+}
+
+#[test]
+fn test_standard_table() {
+    use Choice::*;
+    use Outcome::*;
+
+    let outcomes = [
+        (Rock, Rock, Tie),
+        (Rock, Paper, Win),
+        (Rock, Scissors, Loss),
+        (Paper, Rock, Loss),
+        (Paper, Paper, Tie),
+        (Paper, Scissors, Win),
+        (Scissors, Rock, Win),
+        (Scissors, Paper, Loss),
+        (Scissors, Scissors, Tie),
+    ];
+    for (opponent, player, outcome) in outcomes {
+        assert_eq!(get_match_outcome(STANDARD, opponent, player), outcome);
+        assert_eq!(get_choice_from_outcome(STANDARD, opponent, outcome), player);
+    }
 }
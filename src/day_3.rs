@@ -1,54 +1,95 @@
-fn find_common_item_in_bag(bag: &str) -> Option<char> {
-    let (first_compartment, second_compartment) = bag.split_at(bag.len() / 2);
-    for c in first_compartment.chars() {
-        if second_compartment.contains(c) {
-            return Some(c);
-        }
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::significant_lines,
+    part::{run_day, Part},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+struct PriorityError(char);
+
+impl fmt::Display for PriorityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' is not a valid item", self.0)
     }
+}
+
+impl std::error::Error for PriorityError {}
 
-    None
+fn find_common_item_in_bag(bag: &str) -> Option<char> {
+    let (first_compartment, second_compartment) = bag.split_at(bag.len() / 2);
+    first_compartment
+        .chars()
+        .find(|&c| second_compartment.contains(c))
 }
 
-fn get_priority_from_char(c: char) -> Result<u32, ()> {
-    if c as u8 >= 65 && c as u8 <= 90 {
-        Ok((c as u8 - 38) as u32)
-    } else if c as u8 >= 97 && c as u8 <= 122 {
-        Ok((c as u8 - 96) as u32)
-    } else {
-        Err(())
+fn get_priority_from_char(c: char) -> Result<u32, PriorityError> {
+    match c {
+        'a'..='z' => Ok(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Ok(c as u32 - 'A' as u32 + 27),
+        _ => Err(PriorityError(c)),
     }
 }
 
-fn part_1(compartments: Vec<&str>) -> Result<u32, ()> {
+pub(crate) fn part_1(compartments: Vec<&str>) -> Result<u32> {
     compartments
         .iter()
-        .map(|compartment| match find_common_item_in_bag(compartment) {
-            Some(item) => get_priority_from_char(item),
-            None => panic!("No matching item in rucksack"),
+        .map(|compartment| {
+            let item = find_common_item_in_bag(compartment)
+                .ok_or_else(|| anyhow!("No matching item in rucksack {compartment}"))?;
+            Ok(get_priority_from_char(item)?)
         })
         .sum()
 }
 
-fn part_2(compartments: Vec<&str>) -> Result<u32, ()> {
+pub(crate) fn part_2(compartments: Vec<&str>) -> Result<u32> {
     compartments
         .chunks(3)
         .map(|group| {
-            for c in group[0].chars() {
-                if group[1].contains(c) && group[2].contains(c) {
-                    return get_priority_from_char(c);
-                }
-            }
-
-            panic!("Group without common item");
+            let [a, b, c] = group else {
+                return Err(anyhow!("Group of {} rucksacks", group.len()));
+            };
+            let item = a
+                .chars()
+                .find(|&item| b.contains(item) && c.contains(item))
+                .ok_or_else(|| anyhow!("Group without common item"))?;
+            Ok(get_priority_from_char(item)?)
         })
         .sum()
 }
 
-fn main() {
-    let file = include_str!("test_files/day_3.txt");
-    let input: Vec<&str> = file.lines().collect();
-    println!("Part 1: {}", part_1(input.clone()).unwrap());
-    println!("Part 2: {}", part_2(input).unwrap());
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    let rucksacks = significant_lines(input).map(|(_, line)| line).collect();
+    Ok(part_1(rucksacks)?.into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    let rucksacks = significant_lines(input).map(|(_, line)| line).collect();
+    Ok(part_2(rucksacks)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Rucksack Reorganization: finds the item type shared by compartments and by groups of three",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    fn lines(i: &str) -> Vec<&str> {
+        significant_lines(i).map(|(_, line)| line).collect()
+    }
+    run_day(
+        Part::from_args(&args)?,
+        |i| part_1(lines(i)),
+        |i| part_2(lines(i)),
+        Day::D3.input_for(&args),
+    )
 }
 
 #[test]
@@ -62,3 +103,10 @@ CrZsJsPPZsGzwwsLwLmpwMDw";
 
     assert_eq!(part_1(input.lines().collect()).unwrap(), 157);
 }
+
+#[test]
+fn test_priority() {
+    assert_eq!(get_priority_from_char('a'), Ok(1));
+    assert_eq!(get_priority_from_char('Z'), Ok(52));
+    assert_eq!(get_priority_from_char('7'), Err(PriorityError('7')));
+}
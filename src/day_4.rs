@@ -1,48 +1,80 @@
-use anyhow::Result;
-
-fn get_sections(input: &str) -> Vec<Vec<u32>> {
-    input
-        .lines()
-        .map(|line| {
-            let sections: Vec<&str> = line.split(',').collect();
-            sections
-                .iter()
-                .map(|section| section.split('-'))
-                .flatten()
-                .map(|section| {
-                    section
-                        .parse::<u32>()
-                        .expect("There should always be a number here")
-                })
-                .collect()
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{anyhow, Result};
+
+/// The bounds of both ranges in an `a-b,c-d` pair, in that order.
+fn parse_pair(line: &str) -> Option<[u32; 4]> {
+    let (first, second) = line.split_once(',')?;
+    let (a, b) = first.split_once('-')?;
+    let (c, d) = second.split_once('-')?;
+    let [a, b, c, d] = [a, b, c, d].map(|n| n.parse().ok());
+    Some([a?, b?, c?, d?])
+}
+
+pub(crate) fn get_sections(input: &str) -> Result<Vec<[u32; 4]>> {
+    let Some(input) = non_empty(input) else {
+        return Ok(vec![]);
+    };
+
+    significant_lines(input)
+        .map(|(n, line)| {
+            parse_pair(line)
+                .ok_or_else(|| anyhow!("line {n}: expected a pair like 2-4,6-8, found {line:?}"))
         })
         .collect()
 }
 
-fn part_1(sections: &Vec<Vec<u32>>) -> u32 {
+/// Whether either of the pair's ranges lies entirely within the other.
+fn fully_contains(&[a, b, c, d]: &[u32; 4]) -> bool {
+    ((b >= d) && (a <= c)) || ((b <= d) && (a >= c))
+}
+
+/// Whether the pair's ranges share at least one section.
+fn overlaps(&[a, b, c, d]: &[u32; 4]) -> bool {
+    (a <= d) && (b >= c)
+}
+
+pub(crate) fn part_1(sections: &[[u32; 4]]) -> u32 {
     sections
         .iter()
-        .map(|section| {
-            (((section[1] >= section[3]) && (section[0] <= section[2]))
-                || ((section[1] <= section[3]) && (section[0] >= section[2]))) as u32
-        })
+        .map(|section| fully_contains(section) as u32)
         .sum()
 }
 
-fn part_2(sections: &Vec<Vec<u32>>) -> u32 {
+pub(crate) fn part_2(sections: &[[u32; 4]]) -> u32 {
     sections
         .iter()
-        .map(|section| ((section[0] <= section[3]) && (section[1] >= section[2])) as u32)
+        .map(|section| overlaps(section) as u32)
         .sum()
 }
 
-fn main() -> Result<()> {
-    let file = &include_str!("test_files/day_4.txt");
-    let sections = get_sections(file);
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(&get_sections(input)?).into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(&get_sections(input)?).into())
+}
 
-    println!("Part 1: {}", part_1(&sections));
-    println!("Part 2: {}", part_2(&sections));
-    Ok(())
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Camp Cleanup: counts section assignments that contain or overlap each other",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    run_day(
+        Part::from_args(&args)?,
+        |i| Ok(part_1(&get_sections(i)?)),
+        |i| Ok(part_2(&get_sections(i)?)),
+        Day::D4.input_for(&args),
+    )
 }
 
 #[test]
@@ -54,7 +86,7 @@ fn test_part_1() {
 6-6,4-6
 2-6,4-8";
 
-    assert_eq!(part_1(&get_sections(input)), 2);
+    assert_eq!(part_1(&get_sections(input).unwrap()), 2);
 }
 
 #[test]
@@ -66,5 +98,59 @@ fn test_part_2() {
 6-6,4-6
 2-6,4-8";
 
-    assert_eq!(part_2(&get_sections(input)), 4);
+    assert_eq!(part_2(&get_sections(input).unwrap()), 4);
+}
+
+#[test]
+fn test_malformed_pairs() {
+    for (input, line) in [
+        ("2-4,6", "2-4,6"),
+        ("2-4,6-8\n2-4", "2-4"),
+        ("2-4,x-8", "2-4,x-8"),
+        ("2-4,6-8,1-2", "2-4,6-8,1-2"),
+    ] {
+        let err = get_sections(input).unwrap_err().to_string();
+        assert!(err.ends_with(&format!("found {line:?}")), "{err}");
+    }
+    assert_eq!(
+        get_sections("2-4,6-8\n2-4").unwrap_err().to_string(),
+        "line 2: expected a pair like 2-4,6-8, found \"2-4\""
+    );
+}
+
+/// A `start-end` range with `start <= end`, built from a start and a length
+/// so shrinking keeps it valid.
+#[cfg(test)]
+fn range() -> impl proptest::strategy::Strategy<Value = (u32, u32)> {
+    use proptest::prelude::*;
+    (0..100u32, 0..100u32).prop_map(|(start, len)| (start, start + len))
+}
+
+#[cfg(test)]
+fn pair() -> impl proptest::strategy::Strategy<Value = [u32; 4]> {
+    use proptest::prelude::*;
+    (range(), range()).prop_map(|((a, b), (c, d))| [a, b, c, d])
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_contains_implies_overlaps(section in pair()) {
+        proptest::prop_assert!(!fully_contains(&section) || overlaps(&section));
+    }
+
+    #[test]
+    fn test_relations_are_symmetric(section in pair()) {
+        let [a, b, c, d] = section;
+        let swapped = [c, d, a, b];
+        proptest::prop_assert_eq!(overlaps(&section), overlaps(&swapped));
+        proptest::prop_assert_eq!(fully_contains(&section), fully_contains(&swapped));
+    }
+
+    #[test]
+    fn test_counts_bounded(sections in proptest::collection::vec(pair(), 0..50)) {
+        let lines = sections.len() as u32;
+        proptest::prop_assert!(part_1(&sections) <= part_2(&sections));
+        proptest::prop_assert!(part_2(&sections) <= lines);
+    }
 }
@@ -1,38 +1,91 @@
+use std::io::{self, BufRead};
+
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::non_empty,
+    part::{run_day, Part},
+};
+use anyhow::{anyhow, bail, Context, Result};
 use itertools::Itertools;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take, take_while1};
-use nom::combinator::{all_consuming, map, map_res};
+use nom::combinator::{all_consuming, map, map_opt, map_res};
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, preceded, tuple};
 use nom::{Finish, IResult};
 
+#[derive(Debug, Copy, Clone)]
 struct Instruction {
     src: usize,
     dest: usize,
     n: usize,
 }
 
-#[derive(Debug)]
+/// How a crane moves several crates at once.
+#[derive(Debug, Copy, Clone)]
+enum Crane {
+    OneByOne,
+    Bulk,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Containers {
     stacks: Vec<Vec<char>>,
 }
 
 impl Containers {
-    fn from_picture(pic: &str) -> Containers {
-        let v: Vec<_> = pic
-            .lines()
-            .by_ref()
-            .map_while(|line| {
-                all_consuming(parse_crate_line)(line)
-                    .finish()
-                    .ok()
-                    .map(|(_, line)| line)
-            })
-            .collect();
+    /// Reads the crate lines and, if there is one, the numbered legend
+    /// under them. With a legend the stack count comes from it and every
+    /// crate line has to fit; without one it is the widest crate line.
+    fn from_picture(pic: &str) -> Result<Containers> {
+        let mut lines = pic.lines().map(str::trim_end).enumerate();
+        let mut rows = vec![];
+        let mut legend = None;
+        for (i, line) in lines.by_ref() {
+            if let Ok((_, row)) = all_consuming(parse_crate_line)(line).finish() {
+                rows.push(row);
+                continue;
+            }
+            legend = Some(parse_legend(line).with_context(|| format!("line {}", i + 1))?);
+            break;
+        }
+        if let Some((i, line)) = lines.find(|(_, line)| !line.is_empty()) {
+            bail!("line {}: unexpected line after the legend {line:?}", i + 1);
+        }
 
-        Containers {
-            stacks: transpose(v),
+        let widest = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let count = match legend {
+            Some(count) if count < widest => {
+                bail!("the legend numbers {count} stacks but a crate line has {widest}")
+            }
+            Some(count) => count,
+            None if widest == 0 => bail!("no crates or legend in the picture"),
+            None => widest,
+        };
+        for row in &mut rows {
+            row.resize(count, None);
         }
+
+        Ok(Containers {
+            stacks: transpose(rows, count),
+        })
+    }
+
+    /// Reads the stack picture from `r` up to the blank line, leaving the
+    /// moves unread.
+    fn from_reader(r: &mut impl BufRead) -> Result<Containers> {
+        let mut pic = String::new();
+        for line in r.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                break;
+            }
+            pic.push_str(&line);
+            pic.push('\n');
+        }
+
+        Self::from_picture(&pic)
     }
 
     fn move_containers_one_by_one(&mut self, m: &Instruction) {
@@ -51,25 +104,60 @@ impl Containers {
         dest_stack.extend(tmp);
     }
 
-    fn perform_instructions_p1(&mut self, instructions: &Vec<Instruction>) {
-        instructions.iter().for_each(|inst| {
-            self.move_containers_one_by_one(inst);
-        });
+    /// Makes every move in turn, stopping at the first one the stacks can't
+    /// make.
+    fn perform_instructions(&mut self, instructions: &[Instruction], crane: Crane) -> Result<()> {
+        for (i, m) in instructions.iter().enumerate() {
+            self.check(m).with_context(|| format!("move {}", i + 1))?;
+            self.apply(m, crane);
+        }
+        Ok(())
+    }
+
+    /// Fails instead of panicking on a move the stacks can't make.
+    fn check(&self, m: &Instruction) -> Result<()> {
+        let count = self.stacks.len();
+        let stack = |i: usize| {
+            self.stacks
+                .get(i)
+                .ok_or_else(|| anyhow!("there is no stack {}, only 1 to {count}", i + 1))
+        };
+        let src = stack(m.src)?;
+        stack(m.dest)?;
+        let have = src.len();
+        if have < m.n {
+            bail!("stack {} only has {have} crates", m.src + 1);
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, m: &Instruction, crane: Crane) {
+        match crane {
+            Crane::OneByOne => self.move_containers_one_by_one(m),
+            Crane::Bulk => self.move_containers_in_bulk(m),
+        }
     }
 
-    fn perform_instructions_p2(&mut self, instructions: &Vec<Instruction>) {
-        instructions.iter().for_each(|inst| {
-            self.move_containers_in_bulk(inst);
-        });
+    /// Applies each move as soon as it is parsed, stopping at the first bad
+    /// one.
+    fn apply_stream(
+        &mut self,
+        moves: impl Iterator<Item = Result<Instruction>>,
+        crane: Crane,
+    ) -> Result<()> {
+        for (i, m) in moves.enumerate() {
+            let m = m?;
+            self.check(&m).with_context(|| format!("move {}", i + 1))?;
+            self.apply(&m, crane);
+        }
+
+        Ok(())
     }
 
     fn get_top_stacks(&self) -> String {
         self.stacks
             .iter()
-            .map(|stack| match stack.last() {
-                Some(top) => top,
-                None => &' ',
-            })
+            .map(|stack| stack.last().unwrap_or(&' '))
             .collect::<String>()
     }
 }
@@ -98,8 +186,25 @@ fn parse_number(input: &str) -> IResult<&str, usize> {
     })(input)
 }
 
+/// The stack count from a ` 1   2   3 ` legend, which has to number the
+/// stacks from 1 in order.
+fn parse_legend(line: &str) -> Result<usize> {
+    let mut count = 0;
+    for n in line.split_whitespace() {
+        if n.parse::<usize>().ok() != Some(count + 1) {
+            bail!("expected a crate line or the legend, found {line:?}");
+        }
+        count += 1;
+    }
+    if count == 0 {
+        bail!("expected a crate line or the legend, found {line:?}");
+    }
+    Ok(count)
+}
+
+/// Stacks are numbered from 1, so there is no stack 0.
 fn parse_pile_number(input: &str) -> IResult<&str, usize> {
-    map(parse_number, |i| i - 1)(input)
+    map_opt(parse_number, |i| i.checked_sub(1))(input)
 }
 
 fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
@@ -113,52 +218,173 @@ fn parse_instruction(input: &str) -> IResult<&str, Instruction> {
     )(input)
 }
 
-fn transpose<T>(v: Vec<Vec<Option<T>>>) -> Vec<Vec<T>> {
-    let len = v[0].len();
+/// Parses the moves from `r` one line at a time.
+fn parse_moves(r: impl BufRead) -> impl Iterator<Item = Result<Instruction>> {
+    r.lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|(i, line)| {
+            let line = line?;
+            let parsed = all_consuming(parse_instruction)(&line).finish();
+            parsed
+                .map(|(_, inst)| inst)
+                .map_err(|_| anyhow!("move {}: invalid instruction {line:?}", i + 1))
+        })
+}
+
+/// Moves typed in one at a time against the loaded stacks.
+struct Repl {
+    containers: Containers,
+    crane: Crane,
+    /// The reverse of every move made so far, newest last. Moving the same
+    /// crates back with the same crane undoes a move with either crane.
+    undo: Vec<Instruction>,
+    history: Vec<String>,
+}
+
+impl Repl {
+    fn new(containers: Containers, crane: Crane) -> Self {
+        Self {
+            containers,
+            crane,
+            undo: vec![],
+            history: vec![],
+        }
+    }
+
+    /// Runs one command and returns what to print for it.
+    fn execute(&mut self, line: &str) -> Result<String> {
+        let line = line.trim();
+        match line {
+            "undo" => {
+                let Some(m) = self.undo.pop() else {
+                    bail!("nothing to undo");
+                };
+                self.containers.apply(&m, self.crane);
+            }
+            "history" => return Ok(self.history.join("\n")),
+            _ => {
+                let (_, m) = all_consuming(parse_instruction)(line)
+                    .finish()
+                    .map_err(|_| anyhow!("expected `move N from A to B`, `undo` or `history`"))?;
+                self.containers.check(&m)?;
+                self.containers.apply(&m, self.crane);
+                self.undo.push(Instruction {
+                    src: m.dest,
+                    dest: m.src,
+                    n: m.n,
+                });
+            }
+        }
+        self.history.push(line.to_string());
+        Ok(self.containers.get_top_stacks())
+    }
+}
+
+fn transpose<T>(v: Vec<Vec<Option<T>>>, len: usize) -> Vec<Vec<T>> {
     let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
     (0..len)
         .map(|_| {
             iters
                 .iter_mut()
                 .rev()
-                .filter_map(|n| n.next().unwrap())
+                .filter_map(|n| n.next().flatten())
                 .collect::<Vec<T>>()
         })
         .collect()
 }
 
-fn create_container_and_instructions(input: &str) -> (Containers, Vec<Instruction>) {
+fn create_container_and_instructions(input: &str) -> Result<(Containers, Vec<Instruction>)> {
     let (picture, moves) = input
         .split("\n\n")
         .collect_tuple::<(&str, &str)>()
-        .expect("Input should have exactly two sections");
+        .ok_or_else(|| anyhow!("expected the stacks and the moves separated by a blank line"))?;
 
-    let containers = Containers::from_picture(picture);
-    let instructions: Vec<_> = moves
-        .lines()
-        .map(|line| all_consuming(parse_instruction)(line).finish().unwrap().1)
-        .collect();
+    let containers = Containers::from_picture(picture)?;
+    let instructions = parse_moves(moves.as_bytes()).collect::<Result<Vec<_>>>()?;
 
-    (containers, instructions)
+    Ok((containers, instructions))
 }
 
-fn part_1(input: &str) -> String {
-    let (mut containers, instructions) = create_container_and_instructions(input);
-    containers.perform_instructions_p1(&instructions);
-    containers.get_top_stacks()
+pub(crate) fn part_1(input: &str) -> Result<String> {
+    let Some(input) = non_empty(input) else {
+        return Ok(String::new());
+    };
+
+    let (mut containers, instructions) = create_container_and_instructions(input)?;
+    containers.perform_instructions(&instructions, Crane::OneByOne)?;
+    Ok(containers.get_top_stacks())
 }
 
-fn part_2(input: &str) -> String {
-    let (mut containers, instructions) = create_container_and_instructions(input);
-    containers.perform_instructions_p2(&instructions);
-    containers.get_top_stacks()
+pub(crate) fn part_2(input: &str) -> Result<String> {
+    let Some(input) = non_empty(input) else {
+        return Ok(String::new());
+    };
+
+    let (mut containers, instructions) = create_container_and_instructions(input)?;
+    containers.perform_instructions(&instructions, Crane::Bulk)?;
+    Ok(containers.get_top_stacks())
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(input)?.into())
 }
 
-fn main() {
-    let input = &include_str!("test_files/day_5.txt");
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(input)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Supply Stacks: moves crates between stacks one at a time, then in bulk",
+        answers: [AnswerKind::Text, AnswerKind::Text],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--stdin") {
+        let (crane, part) = if args.iter().any(|arg| arg == "--bulk") {
+            (Crane::Bulk, 2)
+        } else {
+            (Crane::OneByOne, 1)
+        };
+        let mut stdin = io::stdin().lock();
+        let mut containers = Containers::from_reader(&mut stdin)?;
+        containers.apply_stream(parse_moves(stdin), crane)?;
+        println!("Part {part}: {}", containers.get_top_stacks());
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--repl") {
+        let crane = if args.iter().any(|arg| arg == "--bulk") {
+            Crane::Bulk
+        } else {
+            Crane::OneByOne
+        };
+        let (containers, _) = create_container_and_instructions(Day::D5.input_for(&args))?;
+        let mut repl = Repl::new(containers, crane);
+        println!("{}", repl.containers.get_top_stacks());
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            println!("> {}", line.trim());
+            match repl.execute(&line) {
+                Ok(out) => println!("{out}"),
+                Err(e) => println!("error: {e}"),
+            }
+        }
+        return Ok(());
+    }
 
-    println!("Part 1: {}", part_1(input));
-    println!("Part 2: {}", part_2(input));
+    run_day(
+        Part::from_args(&args)?,
+        part_1,
+        part_2,
+        Day::D5.input_for(&args),
+    )
 }
 
 #[test]
@@ -173,7 +399,7 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2";
 
-    assert_eq!(part_1(input), "CMZ");
+    assert_eq!(part_1(input).unwrap(), "CMZ");
 }
 
 #[test]
@@ -188,5 +414,140 @@ move 3 from 1 to 3
 move 2 from 2 to 1
 move 1 from 1 to 2";
 
-    assert_eq!(part_2(input), "MCD");
+    assert_eq!(part_2(input).unwrap(), "MCD");
+}
+
+#[test]
+fn test_apply_stream() {
+    let input = include_str!("test_files/day_5_test.txt");
+    let stream = |crane| {
+        let mut r = io::Cursor::new(input);
+        let mut containers = Containers::from_reader(&mut r).unwrap();
+        containers.apply_stream(parse_moves(r), crane).unwrap();
+        containers.get_top_stacks()
+    };
+
+    assert_eq!(stream(Crane::OneByOne), part_1(input).unwrap());
+    assert_eq!(stream(Crane::Bulk), part_2(input).unwrap());
+    assert_eq!(stream(Crane::OneByOne), "CMZ");
+
+    let mut containers = Containers::from_picture("[A] [B]\n 1   2 \n").unwrap();
+    let err = containers
+        .apply_stream(
+            parse_moves(io::Cursor::new(
+                "move 1 from 1 to 2\nmove one from 2 to 1\n",
+            )),
+            Crane::OneByOne,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "move 2: invalid instruction \"move one from 2 to 1\""
+    );
+    assert_eq!(containers.get_top_stacks(), " A");
+}
+
+#[test]
+fn test_malformed_input() {
+    let picture = "[A] [B]\n 1   2 \n";
+    let err = |input: &str| format!("{:#}", part_1(input).unwrap_err());
+
+    assert_eq!(
+        err("[A] [B]\n 1   2 \nmove 1 from 1 to 2"),
+        "expected the stacks and the moves separated by a blank line"
+    );
+    assert_eq!(
+        err(&format!("{picture}\nmove 1 from 1 to 2\nmove x")),
+        "move 2: invalid instruction \"move x\""
+    );
+    assert_eq!(
+        err(&format!("{picture}\nmove 1 from 0 to 2")),
+        "move 1: invalid instruction \"move 1 from 0 to 2\""
+    );
+    assert_eq!(
+        err(&format!("{picture}\nmove 1 from 1 to 3")),
+        "move 1: there is no stack 3, only 1 to 2"
+    );
+    assert_eq!(
+        format!(
+            "{:#}",
+            part_2(&format!(
+                "{picture}\nmove 1 from 1 to 2\nmove 3 from 2 to 1"
+            ))
+            .unwrap_err()
+        ),
+        "move 2: stack 2 only has 2 crates"
+    );
+
+    let mut containers = Containers::from_picture(picture).unwrap();
+    let moves = parse_moves(io::Cursor::new("move 2 from 1 to 2\n"));
+    assert!(containers.apply_stream(moves, Crane::OneByOne).is_err());
+    assert_eq!(containers.get_top_stacks(), "AB");
+}
+
+#[test]
+fn test_transpose_ragged_rows() {
+    let rows = vec![vec![Some('D')], vec![Some('N'), Some('C'), None]];
+    assert_eq!(transpose(rows, 3), vec![vec!['N', 'D'], vec!['C'], vec![]]);
+}
+
+#[test]
+fn test_picture_legend() {
+    let with_legend = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n";
+    let without_legend = "    [D]\n[N] [C]\n[Z] [M] [P]\n";
+    let stacks = |pic| Containers::from_picture(pic).unwrap().stacks;
+
+    assert_eq!(stacks(with_legend), stacks(without_legend));
+    assert_eq!(
+        stacks(with_legend),
+        vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+    );
+
+    // The legend can number stacks that have no crates yet.
+    assert_eq!(stacks("[A]\n 1   2 \n"), vec![vec!['A'], vec![]]);
+
+    let err = |pic| format!("{:#}", Containers::from_picture(pic).unwrap_err());
+    assert_eq!(
+        err("[A] [B] [C]\n 1   2 \n"),
+        "the legend numbers 2 stacks but a crate line has 3"
+    );
+    assert_eq!(
+        err("[A] [B]\n 1   3 \n"),
+        "line 2: expected a crate line or the legend, found \" 1   3\""
+    );
+    assert_eq!(
+        err("[A]\n 1 \n[B]\n"),
+        "line 3: unexpected line after the legend \"[B]\""
+    );
+    assert_eq!(err(""), "no crates or legend in the picture");
+}
+
+#[test]
+fn test_repl_undo() {
+    let input = include_str!("test_files/day_5_test.txt");
+    for crane in [Crane::OneByOne, Crane::Bulk] {
+        let (containers, _) = create_container_and_instructions(input).unwrap();
+        let mut repl = Repl::new(containers.clone(), crane);
+
+        let tops = match crane {
+            Crane::OneByOne => ["CMP", "DMC"],
+            Crane::Bulk => ["DMP", "CMD"],
+        };
+        assert_eq!(repl.execute("move 2 from 2 to 1").unwrap(), tops[0]);
+        assert_ne!(repl.containers, containers);
+        assert_eq!(repl.execute("move 1 from 1 to 3").unwrap(), tops[1]);
+        repl.execute("undo").unwrap();
+        repl.execute("undo").unwrap();
+        assert_eq!(repl.containers, containers);
+        assert!(repl.execute("undo").is_err());
+
+        assert!(repl.execute("move 4 from 2 to 1").is_err());
+        assert!(repl.execute("move 1 from 4 to 1").is_err());
+        assert!(repl.execute("shuffle").is_err());
+        assert_eq!(repl.containers, containers);
+        assert_eq!(
+            repl.execute("history").unwrap(),
+            "move 2 from 2 to 1\nmove 1 from 1 to 3\nundo\nundo"
+        );
+    }
 }
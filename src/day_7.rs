@@ -1,6 +1,14 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{bail, Context, Result};
+
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::bytes::complete::take_while1;
@@ -10,15 +18,19 @@ use nom::sequence::{preceded, separated_pair};
 use nom::Finish;
 use nom::IResult;
 use std::cell::RefCell;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
+
+pub(crate) type NodeContainer = Rc<RefCell<Node>>;
 
-type NodeContainer = Rc<RefCell<Node>>;
+pub(crate) const TOTAL_SPACE: u32 = 70000000;
+pub(crate) const NEEDED_FREE_SPACE: u32 = 30000000;
 
+/// Parents are held weakly so that a tree is freed once its root is dropped.
 #[derive(Default, Clone)]
-struct Node {
+pub(crate) struct Node {
     pub size: u32,
     pub children: HashMap<PathBuf, NodeContainer>,
-    pub parent: Option<NodeContainer>,
+    pub parent: Option<Weak<RefCell<Node>>>,
 }
 
 impl Node {
@@ -35,6 +47,65 @@ impl Node {
     }
 }
 
+/// The tree flattened into vectors in depth-first order, so every subtree
+/// is a contiguous run of indices. Unlike `Node` this is `Send`, so subtrees
+/// can be summed on other threads.
+#[cfg(feature = "rayon")]
+struct FlatTree {
+    /// Each node's own file size, 0 for directories.
+    sizes: Vec<u32>,
+    children: Vec<Vec<usize>>,
+    /// How many nodes each subtree holds, itself included.
+    subtree_len: Vec<usize>,
+}
+
+#[cfg(feature = "rayon")]
+impl FlatTree {
+    const ROOT: usize = 0;
+    /// Subtrees smaller than this are summed on the current thread, since
+    /// splitting them costs more than it saves.
+    const PARALLEL_MIN_NODES: usize = 1024;
+
+    fn new(root: &NodeContainer) -> Self {
+        let mut tree = Self {
+            sizes: vec![],
+            children: vec![],
+            subtree_len: vec![],
+        };
+        tree.push(root);
+        tree
+    }
+
+    fn push(&mut self, node: &NodeContainer) -> usize {
+        let idx = self.sizes.len();
+        self.sizes.push(node.borrow().size);
+        self.children.push(vec![]);
+        self.subtree_len.push(0);
+
+        let children = node.borrow().children.values().cloned().collect::<Vec<_>>();
+        self.children[idx] = children.iter().map(|c| self.push(c)).collect();
+        self.subtree_len[idx] = self.sizes.len() - idx;
+        idx
+    }
+
+    fn total_size(&self, idx: usize) -> u32 {
+        self.sizes[idx..idx + self.subtree_len[idx]].iter().sum()
+    }
+
+    fn total_size_parallel(&self, idx: usize) -> u32 {
+        use rayon::prelude::*;
+
+        if self.subtree_len[idx] < Self::PARALLEL_MIN_NODES {
+            return self.total_size(idx);
+        }
+        self.children[idx]
+            .par_iter()
+            .map(|&c| self.total_size_parallel(c))
+            .sum::<u32>()
+            + self.sizes[idx]
+    }
+}
+
 struct Ls;
 struct Cd(PathBuf);
 enum Command {
@@ -55,10 +126,7 @@ impl From<Cd> for Command {
 }
 
 fn parse_path(input: &str) -> IResult<&str, PathBuf> {
-    map(
-        take_while1(|c: char| "abcdefghijklmnopqrstuvwxyz./".contains(c)),
-        Into::into,
-    )(input)
+    map(take_while1(|c: char| !c.is_whitespace()), Into::into)(input)
 }
 
 fn parse_ls(input: &str) -> IResult<&str, Ls> {
@@ -121,21 +189,30 @@ fn get_subdirs(n: NodeContainer) -> Box<dyn Iterator<Item = NodeContainer>> {
     )
 }
 
-fn create_tree(input: &str) -> NodeContainer {
-    let lines = input
-        .lines()
-        .map(|line| all_consuming(parse_line)(line).finish().unwrap().1);
+pub(crate) fn create_tree(input: &str) -> Result<NodeContainer> {
     let root = NodeContainer::default();
+    let Some(input) = non_empty(input) else {
+        return Ok(root);
+    };
+
     let mut node = root.clone();
 
-    for line in lines {
+    for (line_no, line) in significant_lines(input) {
+        let parsed = all_consuming(parse_line)(line).finish();
+        let Ok((_, line)) = parsed else {
+            bail!("line {line_no}: unrecognised line {line:?}");
+        };
+
         match line {
             Line::Command(cmd) => match cmd {
                 Command::Ls => {}
                 Command::Cd(path) => match path.to_str() {
                     Some("/") => {}
                     Some("..") => {
-                        let parent = node.borrow().parent.clone().unwrap();
+                        let parent = node.borrow().parent.as_ref().and_then(Weak::upgrade);
+                        let Some(parent) = parent else {
+                            bail!("line {line_no}: `cd ..` above the root");
+                        };
                         node = parent;
                     }
                     _ => {
@@ -143,9 +220,13 @@ fn create_tree(input: &str) -> NodeContainer {
                             .as_ref()
                             .borrow_mut()
                             .children
-                            .entry(path)
+                            .entry(path.clone())
                             .or_default()
                             .clone();
+                        if child.borrow().size != 0 {
+                            bail!("line {line_no}: cannot cd into file {}", path.display());
+                        }
+                        child.as_ref().borrow_mut().parent = Some(Rc::downgrade(&node));
                         node = child;
                     }
                 },
@@ -159,7 +240,7 @@ fn create_tree(input: &str) -> NodeContainer {
                         .entry(dir)
                         .or_default()
                         .clone();
-                    entry.as_ref().borrow_mut().parent = Some(node.clone());
+                    entry.as_ref().borrow_mut().parent = Some(Rc::downgrade(&node));
                 }
                 Entry::File(size, file) => {
                     let entry = node
@@ -170,37 +251,189 @@ fn create_tree(input: &str) -> NodeContainer {
                         .or_default()
                         .clone();
                     entry.as_ref().borrow_mut().size = size;
-                    entry.as_ref().borrow_mut().parent = Some(node.clone());
+                    entry.as_ref().borrow_mut().parent = Some(Rc::downgrade(&node));
                 }
             },
         }
     }
 
-    root
+    Ok(root)
 }
 
-fn part_1(fs: NodeContainer) -> u32 {
+pub(crate) fn part_1(fs: NodeContainer) -> u32 {
     get_subdirs(fs)
         .map(|d| d.borrow().total_size())
         .filter(|&s| s <= 100000)
         .sum()
 }
 
-fn part_2(fs: NodeContainer) -> u32 {
-    let total_space = 70000000;
+/// Size of the smallest directory whose deletion leaves at least `needed`
+/// free on a disk of size `total`. Deleting the root always frees the whole
+/// disk, so this only fails if `needed > total`.
+pub(crate) fn smallest_dir_to_free(fs: NodeContainer, total: u32, needed: u32) -> u32 {
     let used_space = fs.borrow().total_size();
-    let free_space = total_space - used_space;
-    let needed_free_space = 30000000;
-    let reclaim_min_amount = needed_free_space - free_space;
+    let free_space = total.saturating_sub(used_space);
+    let reclaim_min_amount = needed.saturating_sub(free_space);
 
-    get_subdirs(fs).map(|d| d.borrow().total_size())
+    get_subdirs(fs)
+        .map(|d| d.borrow().total_size())
         .filter(|&s| s >= reclaim_min_amount)
-        .min().unwrap()
+        .min()
+        .expect("needed space exceeds the disk size")
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(create_tree(input)?).into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    let root = create_tree(input)?;
+    Ok(smallest_dir_to_free(root, TOTAL_SPACE, NEEDED_FREE_SPACE).into())
 }
 
-fn main() {
-    let input = &include_str!("test_files/day_7.txt");
-    let root = create_tree(input);
-    println!("Part 1: {}", part_1(root.clone()));
-    println!("Part 2: {}", part_2(root));
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "No Space Left On Device: rebuilds a directory tree from a terminal log",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
+
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let flag = |name: &str, default: u32| -> Result<u32> {
+        let Some(i) = args.iter().position(|arg| arg == name) else {
+            return Ok(default);
+        };
+        let value = args
+            .get(i + 1)
+            .with_context(|| format!("Usage: {name} SIZE"))?;
+        Ok(value.parse()?)
+    };
+    let total = flag("--total", TOTAL_SPACE)?;
+    let needed = flag("--needed", NEEDED_FREE_SPACE)?;
+    if needed > total {
+        bail!("--needed ({needed}) is larger than --total ({total})");
+    }
+
+    let input = Day::D7.input_for(&args);
+    let root = create_tree(input)?;
+    #[cfg(feature = "rayon")]
+    if args.iter().any(|arg| arg == "--parallel") {
+        let tree = FlatTree::new(&root);
+        println!("Total size: {}", tree.total_size_parallel(FlatTree::ROOT));
+    }
+    run_day(
+        Part::from_args(&args)?,
+        |_| Ok(part_1(root.clone())),
+        |_| Ok(smallest_dir_to_free(root.clone(), total, needed)),
+        input,
+    )
+}
+
+#[test]
+fn test_mixed_case_names() {
+    let input = "$ cd /
+$ ls
+dir Data_1
+14848514 BFODJH.dat
+$ cd Data_1
+$ ls
+100 file_1
+200 File2.TXT";
+    let root = create_tree(input).unwrap();
+
+    assert!(root
+        .borrow()
+        .children
+        .contains_key(&PathBuf::from("BFODJH.dat")));
+    assert_eq!(root.borrow().total_size(), 14848814);
+    assert_eq!(part_1(root), 300);
+}
+
+#[test]
+fn test_smallest_dir_to_free() {
+    let input = "$ cd /
+$ ls
+dir a
+dir b
+100 x
+$ cd a
+$ ls
+300 y
+$ cd ..
+$ cd b
+$ ls
+dir c
+50 z
+$ cd c
+$ ls
+500 w";
+    let root = create_tree(input).unwrap();
+    assert_eq!(root.borrow().total_size(), 950);
+
+    // 50 free, so at least 350 has to go: b (550) and c (500) both do.
+    assert_eq!(smallest_dir_to_free(root.clone(), 1000, 400), 500);
+    // Only 50 has to go, which a (300) already covers.
+    assert_eq!(smallest_dir_to_free(root.clone(), 1000, 100), 300);
+    // Only deleting everything frees 1000.
+    assert_eq!(smallest_dir_to_free(root, 1000, 1000), 950);
+}
+
+#[test]
+fn test_invalid_navigation() {
+    let input = "$ cd /
+$ ls
+100 a
+$ cd a";
+    let err = create_tree(input).err().unwrap();
+    assert_eq!(err.to_string(), "line 4: cannot cd into file a");
+
+    let err = create_tree("$ cd /\n$ cd ..").err().unwrap();
+    assert_eq!(err.to_string(), "line 2: `cd ..` above the root");
+
+    let err = create_tree("$ cd /\n$ rm -rf").err().unwrap();
+    assert_eq!(err.to_string(), "line 2: unrecognised line \"$ rm -rf\"");
+}
+
+#[test]
+fn test_tree_is_freed() {
+    let root = create_tree("$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n10 b").unwrap();
+    let a = Rc::downgrade(&root.borrow().children[&PathBuf::from("a")]);
+    drop(root);
+    assert!(a.upgrade().is_none());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_total_size_parallel() {
+    // Every directory holds `fanout` subdirectories and one file, down to
+    // `depth` levels.
+    fn synthetic(depth: u32, fanout: u32, next_size: &mut u32) -> NodeContainer {
+        let node = NodeContainer::default();
+        *next_size += 1;
+        let file = Node {
+            size: *next_size,
+            ..Default::default()
+        };
+        let mut children = HashMap::from([(PathBuf::from("file"), Rc::new(RefCell::new(file)))]);
+        if depth > 0 {
+            for i in 0..fanout {
+                let child = synthetic(depth - 1, fanout, next_size);
+                children.insert(PathBuf::from(format!("d{i}")), child);
+            }
+        }
+        node.borrow_mut().children = children;
+        node
+    }
+
+    let root = synthetic(5, 6, &mut 0);
+    let tree = FlatTree::new(&root);
+    assert!(tree.sizes.len() > 10 * FlatTree::PARALLEL_MIN_NODES);
+
+    let expected = root.borrow().total_size();
+    assert_eq!(tree.total_size(FlatTree::ROOT), expected);
+    assert_eq!(tree.total_size_parallel(FlatTree::ROOT), expected);
+    for idx in tree.children[FlatTree::ROOT].iter().copied() {
+        assert_eq!(tree.total_size_parallel(idx), tree.total_size(idx));
+    }
 }
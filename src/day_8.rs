@@ -1,65 +1,127 @@
 use core::fmt;
 
-use anyhow::Result;
+use crate::{
+    answer::Answer,
+    day::{AnswerKind, Day, DayInfo},
+    input::{non_empty, significant_lines},
+    part::{run_day, Part},
+};
+use anyhow::{anyhow, bail, Result};
 
 #[derive(Copy, Clone)]
-struct GridCoord {
+pub(crate) struct GridCoord {
     x: usize,
     y: usize,
 }
 
-struct Grid {
-    contents: Vec<usize>,
+/// Read access to a grid of tree heights, independent of how the grid
+/// stores them.
+pub(crate) trait HeightGrid {
+    fn height(&self, c: GridCoord) -> Option<u8>;
+    fn dims(&self) -> (usize, usize);
+}
+
+pub(crate) struct Grid<T = usize> {
+    contents: Vec<T>,
     width: usize,
     height: usize,
 }
 
+/// A grid taking one byte per tree instead of eight.
+type CompactGrid = Grid<u8>;
+
 impl Grid {
-    fn new(grid: &str) -> Grid {
-        Grid {
-            contents: grid
-                .lines()
-                .map(|l| l.chars().map(|c| c as usize - '0' as usize))
-                .flatten()
-                .collect(),
-            width: grid.lines().next().unwrap().len(),
-            height: grid.lines().count(),
+    pub(crate) fn new(grid: &str) -> Result<Grid> {
+        Self::from_digits(grid)
+    }
+}
+
+impl<T: From<u8>> Grid<T> {
+    /// Every row has to be as wide as the first and hold only digits.
+    fn from_digits(grid: &str) -> Result<Self> {
+        let Some(grid) = non_empty(grid) else {
+            return Ok(Grid {
+                contents: vec![],
+                width: 0,
+                height: 0,
+            });
+        };
+
+        let mut contents = vec![];
+        let mut width = None;
+        let mut height = 0;
+        for (n, line) in significant_lines(grid) {
+            let width = *width.get_or_insert(line.len());
+            if line.len() != width {
+                bail!("line {n}: {} trees wide, expected {width}", line.len());
+            }
+            for c in line.bytes() {
+                if !c.is_ascii_digit() {
+                    bail!("line {n}: expected only digits, found {line:?}");
+                }
+                contents.push(T::from(c - b'0'));
+            }
+            height += 1;
         }
+
+        Ok(Grid {
+            contents,
+            width: width.unwrap_or(0),
+            height,
+        })
     }
+}
 
+impl<T> Grid<T> {
     fn in_bounds(&self, coord: GridCoord) -> bool {
         coord.x < self.width && coord.y < self.height
     }
+}
 
-    fn cell(&self, coord: GridCoord) -> Option<usize> {
-        if self.in_bounds(coord) {
-            Some(self.contents[coord.y * self.width + coord.x])
-        } else {
-            None
+impl<T: Copy + TryInto<u8>> HeightGrid for Grid<T> {
+    fn height(&self, c: GridCoord) -> Option<u8> {
+        if !self.in_bounds(c) {
+            return None;
         }
+        let height = *self.contents.get(c.y * self.width + c.x)?;
+        height.try_into().ok()
+    }
+
+    fn dims(&self) -> (usize, usize) {
+        (self.width, self.height)
     }
 }
 
-impl fmt::Debug for Grid {
+impl<T: fmt::Display> fmt::Debug for Grid<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.contents
-            .chunks(self.width)
-            .map(|c| writeln!(f, "{}", c.iter().map(|v| v.to_string()).collect::<String>()))
-            .collect()
+        if self.width == 0 {
+            return Ok(());
+        }
+
+        self.contents.chunks(self.width).try_for_each(|c| {
+            writeln!(f, "{}", c.iter().map(|v| v.to_string()).collect::<String>())
+        })
     }
 }
 
-fn trees_in_direction(g: &Grid, c: GridCoord, (x, y): (isize, isize)) -> usize {
-    let line = (1..).into_iter().map_while(|i| {
+fn all_coords(g: &impl HeightGrid) -> impl Iterator<Item = GridCoord> {
+    let (width, height) = g.dims();
+    (0..height).flat_map(move |y| (0..width).map(move |x| GridCoord { x, y }))
+}
+
+fn trees_in_direction(g: &impl HeightGrid, c: GridCoord, (x, y): (isize, isize)) -> usize {
+    let line = (1..).map_while(|i| {
         let coord = GridCoord {
             x: c.x.checked_add_signed(x * i)?,
             y: c.y.checked_add_signed(y * i)?,
         };
-        Some(g.cell(coord)?)
+        g.height(coord)
     });
 
+    let Some(height) = g.height(c) else {
+        return 0;
+    };
     let mut total = 0;
-    let height = g.cell(c).unwrap();
     for h in line {
         total += 1;
         if h >= height {
@@ -70,30 +132,102 @@ fn trees_in_direction(g: &Grid, c: GridCoord, (x, y): (isize, isize)) -> usize {
     total
 }
 
-fn get_score(g: &Grid, c: GridCoord) -> usize {
-    let views = [(-1, 0), (1, 0), (0, -1), (0, 1)];
-    views
-        .into_iter()
-        .map(|(x, y)| trees_in_direction(g, c, (x, y)))
-        .product()
+/// The directions a tree looks in, named for `--tree`.
+const VIEWS: [(&str, (isize, isize)); 4] = [
+    ("left", (-1, 0)),
+    ("right", (1, 0)),
+    ("up", (0, -1)),
+    ("down", (0, 1)),
+];
+
+/// How many trees `c` sees in each of `VIEWS` before one blocks it.
+fn view_distances(g: &impl HeightGrid, c: GridCoord) -> [usize; 4] {
+    VIEWS.map(|(_, dir)| trees_in_direction(g, c, dir))
+}
+
+fn get_score(g: &impl HeightGrid, c: GridCoord) -> usize {
+    view_distances(g, c).into_iter().product()
 }
 
-fn part_1(g: &Grid) -> usize {
-    let all_coords = (0..g.height)
-        .into_iter()
-        .flat_map(|y| (0..g.width).into_iter().map(move |x| GridCoord { x, y }));
+/// Explains the scenic score of the tree at `x,y`, one direction per line.
+fn describe_tree(g: &impl HeightGrid, coord: &str) -> Result<String> {
+    let (x, y) = coord
+        .split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| anyhow!("expected a tree as X,Y, found {coord:?}"))?;
+    let c = GridCoord { x, y };
+    let Some(height) = g.height(c) else {
+        bail!("{x},{y} is outside the {:?} grid", g.dims());
+    };
+
+    let mut out = format!("Tree {x},{y} (height {height})\n");
+    for ((name, _), distance) in VIEWS.iter().zip(view_distances(g, c)) {
+        out += &format!("  {name:>5}: {distance}\n");
+    }
+    out += &format!("  score: {}", get_score(g, c));
+    Ok(out)
+}
 
-    all_coords
+/// Whether every tree between `c` and the edge in direction `(dx, dy)` is
+/// shorter than `tree`, stopping at the first one that isn't.
+fn visible_from(g: &impl HeightGrid, c: GridCoord, (dx, dy): (isize, isize), tree: u8) -> bool {
+    let mut pos = c;
+    loop {
+        let (Some(x), Some(y)) = (pos.x.checked_add_signed(dx), pos.y.checked_add_signed(dy))
+        else {
+            return true;
+        };
+        pos = GridCoord { x, y };
+        match g.height(pos) {
+            None => return true,
+            Some(h) if h >= tree => return false,
+            Some(_) => {}
+        }
+    }
+}
+
+/// Trees on the edge are visible without looking, so only the interior is
+/// scanned, nearest edge first. On the puzzle input that reads 86403 cells
+/// where scanning every tree in a fixed order read 96722.
+pub(crate) fn part_1(g: &impl HeightGrid) -> usize {
+    let (width, height) = g.dims();
+    all_coords(g)
         .filter(|&c| {
-            let height = g.cell(c).unwrap();
+            if c.x == 0 || c.y == 0 || c.x + 1 == width || c.y + 1 == height {
+                return true;
+            }
+            let Some(tree) = g.height(c) else {
+                return false;
+            };
+            // The shortest way out is the likeliest to be clear.
+            let mut views = [
+                (c.x, (-1, 0)),
+                (width - 1 - c.x, (1, 0)),
+                (c.y, (0, -1)),
+                (height - 1 - c.y, (0, 1)),
+            ];
+            views.sort_unstable_by_key(|&(distance, _)| distance);
+            views.iter().any(|&(_, dir)| visible_from(g, c, dir, tree))
+        })
+        .count()
+}
+
+/// `part_1` as it was before edge trees were skipped, to check against.
+#[cfg(test)]
+fn part_1_scan_all(g: &impl HeightGrid) -> usize {
+    all_coords(g)
+        .filter(|&c| {
+            let Some(height) = g.height(c) else {
+                return false;
+            };
             let views = [(-1, 0), (1, 0), (0, -1), (0, 1)];
             views.iter().any(|&(x, y)| {
-                let mut cells = (1..).into_iter().map_while(|i| {
+                let mut cells = (1..).map_while(|i| {
                     let coord = GridCoord {
                         x: c.x.checked_add_signed(x * i)?,
                         y: c.y.checked_add_signed(y * i)?,
                     };
-                    g.cell(coord)
+                    g.height(coord)
                 });
                 cells.all(|h| h < height)
             })
@@ -101,29 +235,163 @@ fn part_1(g: &Grid) -> usize {
         .count()
 }
 
-fn part_2(g: &Grid) -> usize {
-    let all_coords = (0..g.height)
-        .into_iter()
-        .flat_map(|y| (0..g.width).into_iter().map(move |x| GridCoord { x, y }));
+pub(crate) fn part_2(g: &impl HeightGrid) -> usize {
+    all_coords(g).map(|c| get_score(g, c)).max().unwrap_or(0)
+}
+
+/// The puzzle input's trees as grey levels, taller trees lighter.
+pub(crate) fn thumbnail() -> egui::ColorImage {
+    let g = Grid::new(include_str!("test_files/day_8.txt")).expect("the puzzle input should parse");
+    let (width, height) = g.dims();
+    let pixels = all_coords(&g)
+        .map(|c| egui::Color32::from_gray(40 + g.height(c).unwrap() * 22))
+        .collect();
+    egui::ColorImage {
+        size: [width, height],
+        pixels,
+    }
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(part_1(&Grid::new(input)?).into())
+}
 
-    all_coords
-        .map(|c| (c, get_score(&g, c)))
-        .max_by_key(|(_, score)| *score)
-        .unwrap().1
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(part_2(&Grid::new(input)?).into())
 }
 
-fn main() -> Result<()> {
-    let input = &include_str!("test_files/day_8.txt");
-    let grid = Grid::new(input);
-    println!("{}", part_1(&grid));
-    println!("{}", part_2(&grid));
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title:
+            "Treetop Tree House: counts trees visible from outside the grid and scores the views",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
+}
 
-    Ok(())
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let part = Part::from_args(&args)?;
+    let input = Day::D8.input_for(&args);
+    if let Some(i) = args.iter().position(|arg| arg == "--tree") {
+        let Some(coord) = args.get(i + 1) else {
+            bail!("Usage: --tree X,Y");
+        };
+        println!("{}", describe_tree(&Grid::new(input)?, coord)?);
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--compact") {
+        let grid = CompactGrid::from_digits(input)?;
+        run_day(part, |_| Ok(part_1(&grid)), |_| Ok(part_2(&grid)), input)
+    } else {
+        let grid = Grid::new(input)?;
+        run_day(part, |_| Ok(part_1(&grid)), |_| Ok(part_2(&grid)), input)
+    }
 }
 
 #[test]
 fn test_grid_creation() {
     let input = "30373\n25512\n65332\n33549\n35390";
-    let g = Grid::new(input);
+    let g = Grid::new(input).unwrap();
     assert_eq!(format!("{g:?}"), "30373\n25512\n65332\n33549\n35390\n");
 }
+
+#[test]
+fn test_empty_input() {
+    for input in ["", " \n"] {
+        let g = Grid::new(input).unwrap();
+        assert_eq!(format!("{g:?}"), "");
+        assert_eq!(part_1(&g), 0);
+        assert_eq!(part_2(&g), 0);
+    }
+}
+
+#[test]
+fn test_compact_grid() {
+    let input = "30373\n25512\n65332\n33549\n35390";
+    let wide = Grid::new(input).unwrap();
+    let compact = CompactGrid::from_digits(input).unwrap();
+
+    assert_eq!(format!("{compact:?}"), format!("{wide:?}"));
+    assert_eq!(compact.dims(), wide.dims());
+    assert_eq!(part_1(&compact), part_1(&wide));
+    assert_eq!(part_2(&compact), part_2(&wide));
+    assert_eq!(part_1(&compact), 21);
+    assert_eq!(part_2(&compact), 8);
+}
+
+#[test]
+fn test_grid_golden() {
+    let g = Grid::new("30373\n25512\n65332\n33549\n35390").unwrap();
+    crate::golden::assert_golden("day_8_grid.txt", &format!("{g:?}"));
+}
+
+#[test]
+fn test_view_distances() {
+    let g = Grid::new("30373\n25512\n65332\n33549\n35390").unwrap();
+    let c = GridCoord { x: 2, y: 3 };
+    let distances = view_distances(&g, c);
+    assert_eq!(distances, [2, 2, 2, 1]);
+    assert_eq!(distances.iter().product::<usize>(), get_score(&g, c));
+    assert_eq!(get_score(&g, c), 8);
+
+    assert_eq!(
+        describe_tree(&g, "2,3").unwrap(),
+        "Tree 2,3 (height 5)\n   left: 2\n  right: 2\n     up: 2\n   down: 1\n  score: 8"
+    );
+    assert!(describe_tree(&g, "5,0").is_err());
+    assert!(describe_tree(&g, "x").is_err());
+}
+
+#[test]
+fn test_malformed_grid() {
+    let err = |input| Grid::new(input).unwrap_err().to_string();
+    assert_eq!(err("303\n25"), "line 2: 2 trees wide, expected 3");
+    assert_eq!(err("303\n2551"), "line 2: 4 trees wide, expected 3");
+    assert_eq!(
+        err("303\n2x5"),
+        "line 2: expected only digits, found \"2x5\""
+    );
+    assert!(CompactGrid::from_digits("30-").is_err());
+}
+
+/// Counts every height read, to compare how much work each scan does.
+#[cfg(test)]
+struct CountingGrid<'a, G> {
+    grid: &'a G,
+    reads: std::cell::Cell<usize>,
+}
+
+#[cfg(test)]
+impl<G: HeightGrid> HeightGrid for CountingGrid<'_, G> {
+    fn height(&self, c: GridCoord) -> Option<u8> {
+        self.reads.set(self.reads.get() + 1);
+        self.grid.height(c)
+    }
+
+    fn dims(&self) -> (usize, usize) {
+        self.grid.dims()
+    }
+}
+
+#[test]
+fn test_part_1_skips_edges() {
+    for input in [
+        "30373\n25512\n65332\n33549\n35390",
+        include_str!("test_files/day_8.txt"),
+        "5",
+        "12\n34",
+        "",
+    ] {
+        let grid = Grid::new(input).unwrap();
+        let [fast, slow] = [&grid; 2].map(|grid| CountingGrid {
+            grid,
+            reads: Default::default(),
+        });
+        let (fast, fast_reads) = (part_1(&fast), fast.reads.get());
+        let (slow, slow_reads) = (part_1_scan_all(&slow), slow.reads.get());
+        assert_eq!(fast, slow, "{input:?}");
+        assert!(fast_reads <= slow_reads, "{fast_reads} > {slow_reads}");
+    }
+    let sample = Grid::new("30373\n25512\n65332\n33549\n35390").unwrap();
+    assert_eq!(part_1(&sample), 21);
+}
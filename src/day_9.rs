@@ -6,17 +6,46 @@ use nom::{
     sequence::{preceded, tuple},
     Finish, IResult,
 };
-use std::{collections::VecDeque, fmt, time::Duration};
+use std::{collections::VecDeque, fmt, path::Path, time::Duration};
 
-use eframe::{egui, epaint::ahash::HashSet};
-use egui::{Color32, Sense, Slider, Stroke, Vec2};
+use crate::{
+    answer::Answer,
+    coord::Coord,
+    day::{wants_sample, AnswerKind, Day, DayInfo},
+    input::significant_lines,
+    palette::Palette,
+    state,
+    steppable::{self, RateMeter, Steppable},
+    view::ViewTransform,
+};
+use anyhow::{anyhow, bail, Result};
+use eframe::{
+    egui,
+    epaint::ahash::{HashMap, HashSet},
+};
+use egui::{Color32, ColorImage, DragValue, FontId, Rect, Rounding, Sense, Slider, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = "day_9_state.json";
+const EXPORT_FILE: &str = "day_9_visited.png";
+/// Size of one grid cell on screen at zoom level 1.
+const SIDE: f32 = 5.0;
+/// How many steps a newly visited cell takes to fade to its resting color.
+const FADE_STEPS: u64 = 200;
 
-#[derive(Copy, Clone, Hash, Eq, PartialEq)]
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 struct GridCoord {
     x: i32,
     y: i32,
 }
 
+impl GridCoord {
+    /// Both halves in one `u64`, `x` in the high 32 bits.
+    fn packed(self) -> u64 {
+        (self.x as u32 as u64) << 32 | self.y as u32 as u64
+    }
+}
+
 impl fmt::Debug for GridCoord {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
@@ -54,6 +83,24 @@ impl std::ops::Sub for GridCoord {
     }
 }
 
+impl From<GridCoord> for Coord {
+    fn from(value: GridCoord) -> Self {
+        Coord {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
+impl From<Coord> for GridCoord {
+    fn from(value: Coord) -> Self {
+        GridCoord {
+            x: value.x,
+            y: value.y,
+        }
+    }
+}
+
 impl From<GridCoord> for Vec2 {
     fn from(value: GridCoord) -> Self {
         Vec2 {
@@ -63,7 +110,7 @@ impl From<GridCoord> for Vec2 {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 enum Direction {
     Up,
     Down,
@@ -72,6 +119,13 @@ enum Direction {
 }
 
 impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
     fn parse(i: &str) -> IResult<&str, Self> {
         alt((
             value(Direction::Up, tag("U")),
@@ -91,7 +145,48 @@ impl Direction {
     }
 }
 
-#[derive(Debug)]
+/// The letter the puzzle input uses for the direction.
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Direction::Up => "U",
+            Direction::Down => "D",
+            Direction::Left => "L",
+            Direction::Right => "R",
+        };
+        f.write_str(letter)
+    }
+}
+
+/// Rotations for analysing the rope's path. Nothing outside the tests
+/// uses them yet.
+#[cfg(test)]
+impl Direction {
+    /// Inverse of `delta`, for unit steps only.
+    fn from_delta(d: GridCoord) -> Option<Direction> {
+        Self::ALL.into_iter().find(|dir| dir.delta() == d)
+    }
+
+    fn opposite(self) -> Direction {
+        self.turn_right().turn_right()
+    }
+
+    /// Clockwise as drawn, with y pointing down.
+    fn turn_right(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    fn turn_left(self) -> Direction {
+        self.turn_right().turn_right().turn_right()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Instruction {
     dir: Direction,
     dist: u32,
@@ -109,44 +204,485 @@ impl Instruction {
     }
 }
 
+/// The instruction as a line of puzzle input, like `U 4`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.dir, self.dist)
+    }
+}
+
+/// How far the head is told to go in each direction, in total.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MovementSummary {
+    /// Steps per direction, in the order of `Direction::ALL`.
+    steps: [u64; 4],
+    /// Where the head ends up relative to where it started, ignoring any
+    /// wrap.
+    net: GridCoord,
+}
+
+impl fmt::Display for MovementSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (dir, steps) in Direction::ALL.iter().zip(self.steps) {
+            writeln!(f, "{dir:?}: {steps}")?;
+        }
+        writeln!(f, "Net displacement: {}, {}", self.net.x, self.net.y)
+    }
+}
+
+fn movement_summary<'a>(
+    instructions: impl IntoIterator<Item = &'a Instruction>,
+) -> MovementSummary {
+    let mut summary = MovementSummary {
+        steps: [0; 4],
+        net: GridCoord { x: 0, y: 0 },
+    };
+    for inst in instructions {
+        let i = Direction::ALL.iter().position(|&d| d == inst.dir).unwrap();
+        summary.steps[i] += inst.dist as u64;
+        let delta = inst.dir.delta();
+        summary.net += GridCoord {
+            x: delta.x * inst.dist as i32,
+            y: delta.y * inst.dist as i32,
+        };
+    }
+    summary
+}
+
+/// JSON can't key a map by `GridCoord`, so the visited cells are saved as
+/// a list of pairs.
+mod visited_pairs {
+    use super::{GridCoord, HashMap};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<GridCoord, u64>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        s.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<HashMap<GridCoord, u64>, D::Error> {
+        Ok(Vec::<(GridCoord, u64)>::deserialize(d)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// How far one step goes: a single cell, or a whole line like `U 4`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum StepMode {
+    #[default]
+    SubMove,
+    FullInstruction,
+}
+
+#[derive(Serialize, Deserialize)]
 struct Simulation {
     instructions: VecDeque<Instruction>,
+    /// Runs the example from the puzzle text instead of the full input.
+    #[serde(default)]
+    sample: bool,
     knots: [GridCoord; 10],
-    tail_visited: HashSet<GridCoord>,
+    /// The knot whose cells go in `tail_visited`. The rope is always ten
+    /// knots long; the ones past the tail just trail along.
+    #[serde(default = "last_knot")]
+    tail: usize,
+    /// Every cell the tail has been on, with the step it first got there.
+    #[serde(with = "visited_pairs")]
+    tail_visited: HashMap<GridCoord, u64>,
+    /// When set, visited cells go here instead of `tail_visited`, as bare
+    /// keys with no step attached. Enough to count them, in a fraction of
+    /// the memory.
+    #[serde(skip)]
+    packed_visited: Option<HashSet<u64>>,
+    /// Steps taken since the start of the input.
+    steps: u64,
+    /// Draws the newest visited cells brighter, fading with age.
+    fade: bool,
+    #[serde(default)]
+    step_mode: StepMode,
     speed: u32,
     paused: bool,
+    /// Shows the frame and step rates over the simulation.
+    #[serde(default)]
+    show_rate: bool,
+    #[serde(skip)]
+    rate: RateMeter,
     show_sidebar: bool,
-    step: bool,
-    view_origin: Vec2,
-    zoom_level: f32,
+    show_palette: bool,
+    /// The camera, with `origin` measured from the middle of the panel.
+    /// Reset leaves it alone; Reset view puts it back.
+    view: ViewTransform,
+    /// Kept in eframe storage rather than with the rest of the state.
+    #[serde(skip)]
+    palette: Palette,
+    /// Width and height of the torus the rope lives on, if it wraps.
+    wrap: Option<(i32, i32)>,
+    /// Pauses the simulation when a knot lands here.
+    #[serde(default)]
+    breakpoint: Option<GridCoord>,
+    /// Set once a knot has landed on the breakpoint, so it only pauses the
+    /// first time. Moving or clearing the breakpoint resets it.
+    #[serde(default)]
+    breakpoint_hit: bool,
+    /// Draws the path each knot took over its last `trail_len` steps.
+    #[serde(default)]
+    show_trails: bool,
+    #[serde(default = "default_trail_len")]
+    trail_len: usize,
+    /// The most recent positions of each knot, oldest first. Only kept
+    /// while the trails are shown.
+    #[serde(skip)]
+    trails: [VecDeque<GridCoord>; 10],
+    /// Set by the Fit button, since fitting needs the panel size.
+    #[serde(skip)]
+    fit_requested: bool,
+}
+
+fn default_view() -> ViewTransform {
+    ViewTransform {
+        origin: Vec2::ZERO,
+        zoom: 1.0,
+        cell: Vec2::splat(SIDE),
+    }
+}
+
+fn last_knot() -> usize {
+    9
+}
+
+fn default_trail_len() -> usize {
+    20
+}
+
+/// The smallest and largest corner of the box around `cells`.
+fn bounds(mut cells: impl Iterator<Item = GridCoord>) -> Option<(GridCoord, GridCoord)> {
+    let first = cells.next()?;
+    Some(cells.fold((first, first), |(min, max), c| {
+        (
+            GridCoord {
+                x: min.x.min(c.x),
+                y: min.y.min(c.y),
+            },
+            GridCoord {
+                x: max.x.max(c.x),
+                y: max.y.max(c.y),
+            },
+        )
+    }))
+}
+
+/// A move of no distance does nothing, so it is left out.
+fn parse_instructions(input: &str) -> Result<VecDeque<Instruction>> {
+    significant_lines(input)
+        .map(|(n, l)| {
+            let parsed = all_consuming(Instruction::parse)(l).finish();
+            parsed
+                .map(|(_, inst)| inst)
+                .map_err(|_| anyhow!("line {n}: invalid move {l:?}"))
+        })
+        .filter(|inst| inst.as_ref().map_or(true, |inst| inst.dist > 0))
+        .collect()
 }
 
 impl Simulation {
-    fn new() -> Self {
-        let instructions = include_str!("test_files/day_9.txt")
-            .lines()
-            .map(|l| all_consuming(Instruction::parse)(l).finish().unwrap().1)
-            .collect();
+    fn new(palette: Palette) -> Self {
+        Self::with_sample(palette, false)
+    }
 
+    fn with_sample(palette: Palette, sample: bool) -> Self {
+        let input = Day::D9.bundled(sample);
+        Self {
+            sample,
+            ..Self::with_instructions(
+                palette,
+                parse_instructions(input).expect("the puzzle input should parse"),
+            )
+        }
+    }
+
+    fn with_instructions(palette: Palette, instructions: VecDeque<Instruction>) -> Self {
         Self {
             instructions,
+            sample: false,
             knots: [GridCoord { x: 0, y: 0 }; 10],
-            tail_visited: HashSet::default(),
+            tail: last_knot(),
+            tail_visited: HashMap::default(),
+            packed_visited: None,
+            steps: 0,
+            fade: true,
+            step_mode: StepMode::default(),
             speed: 1,
             paused: true,
+            show_rate: false,
+            rate: RateMeter::default(),
             show_sidebar: true,
-            step: false,
-            view_origin: Vec2::default(),
-            zoom_level: 1.0,
+            show_palette: false,
+            view: default_view(),
+            palette,
+            wrap: None,
+            breakpoint: None,
+            breakpoint_hit: false,
+            show_trails: false,
+            trail_len: default_trail_len(),
+            trails: Default::default(),
+            fit_requested: false,
         }
     }
 
+    /// The instructions still to run, as puzzle input. A partly run
+    /// instruction is written with only the moves it has left.
+    fn to_input(&self) -> String {
+        self.instructions
+            .iter()
+            .map(|inst| format!("{inst}\n"))
+            .collect()
+    }
+
+    /// The smallest and largest corner of the box around every visited
+    /// cell, or `None` before the tail has moved.
+    fn visited_bounds(&self) -> Option<(GridCoord, GridCoord)> {
+        bounds(self.tail_visited.keys().copied())
+    }
+
+    /// The box from `min` to `max`, one pixel per cell, with the visited
+    /// cells and optionally the knots drawn in. Cells outside the box are
+    /// left out.
+    fn render(&self, (min, max): (GridCoord, GridCoord), with_knots: bool) -> image::RgbaImage {
+        let size = max - min + GridCoord { x: 1, y: 1 };
+        let mut img = image::RgbaImage::from_pixel(
+            size.x as u32,
+            size.y as u32,
+            image::Rgba(self.palette.air.to_array()),
+        );
+        let knots: &[GridCoord] = if with_knots { &self.knots } else { &[] };
+        let cells = self
+            .tail_visited
+            .keys()
+            .map(|&c| (c, self.palette.visited))
+            .chain(knots.iter().map(|&c| (c, self.palette.knot)));
+        for (c, color) in cells {
+            let p = c - min;
+            if (0..size.x).contains(&p.x) && (0..size.y).contains(&p.y) {
+                img.put_pixel(p.x as u32, p.y as u32, image::Rgba(color.to_array()));
+            }
+        }
+        img
+    }
+
+    /// The visited cells cropped to their bounds, one pixel per cell.
+    fn visited_image(&self) -> Option<image::RgbaImage> {
+        Some(self.render(self.visited_bounds()?, false))
+    }
+
+    /// Runs the remaining instructions without a window, writing the
+    /// visited cells and knots to `dir/frame_00001.png` and on every `every`
+    /// steps. A dry run first finds the box the whole run covers, so every
+    /// frame is the same size and can go straight into a video encoder.
+    /// Returns the number of frames written.
+    fn dump_frames(&mut self, dir: &Path, every: usize) -> Result<usize> {
+        if every == 0 {
+            bail!("--every must be at least 1");
+        }
+
+        let mut dry = Self {
+            instructions: self.instructions.clone(),
+            knots: self.knots,
+            wrap: self.wrap,
+            ..Self::new(self.palette)
+        };
+        let mut cells = dry.knots.to_vec();
+        while !dry.is_done() {
+            dry.step();
+            cells.extend(dry.knots);
+        }
+        let Some(bounds) = bounds(cells.into_iter().chain(self.tail_visited.keys().copied()))
+        else {
+            return Ok(0);
+        };
+
+        std::fs::create_dir_all(dir)?;
+        let mut frames = 0;
+        for step in 1.. {
+            if self.is_done() {
+                break;
+            }
+            self.step();
+            if step % every == 0 {
+                frames += 1;
+                self.render(bounds, true)
+                    .save(dir.join(format!("frame_{frames:05}.png")))?;
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Full strength for a cell visited this step, fading to a third of it
+    /// over `FADE_STEPS`, or always full strength with fading off.
+    fn visited_color(&self, visited_at: u64) -> Color32 {
+        if !self.fade {
+            return self.palette.visited;
+        }
+        let age = self.steps.saturating_sub(visited_at).min(FADE_STEPS);
+        let freshness = 1.0 - age as f32 / FADE_STEPS as f32;
+        self.palette.visited.gamma_multiply(0.35 + 0.65 * freshness)
+    }
+
+    /// Zooms and pans so the visited cells fill a panel of `panel` size.
+    fn fit_view(&mut self, panel: Vec2) {
+        let Some((min, max)) = self.visited_bounds() else {
+            return;
+        };
+        let extent = Vec2::from(max - min + GridCoord { x: 1, y: 1 }) * SIDE;
+        let zoom = (panel / extent).min_elem().clamp(0.1, 10.0);
+        let mid = (Vec2::from(min) + Vec2::from(max)) / 2.0;
+        self.view.zoom = zoom;
+        self.view.origin = -mid * SIDE * zoom;
+    }
+
+    /// The camera for a panel filling `rect`, with the world origin in the
+    /// middle when the view hasn't been panned.
+    fn panel_view(&self, rect: Rect) -> ViewTransform {
+        ViewTransform {
+            origin: rect.center().to_vec2() + self.view.origin,
+            zoom: self.view.zoom.clamp(0.1, 10.0),
+            ..self.view
+        }
+    }
+
+    fn hover_text(&self, c: GridCoord) -> String {
+        let visited = if self.tail_visited.contains_key(&c) {
+            "yes"
+        } else {
+            "no"
+        };
+        format!("hovering ({}, {}) — visited: {visited}", c.x, c.y)
+    }
+
+    fn save_state(&self, path: &Path) -> Result<()> {
+        state::save(self, path)
+    }
+
+    fn load_state(&mut self, path: &Path) -> Result<()> {
+        *self = Self {
+            palette: self.palette,
+            ..state::load(path)?
+        };
+        Ok(())
+    }
+
+    fn wrapped(&self, c: GridCoord) -> GridCoord {
+        match self.wrap {
+            Some((w, h)) => GridCoord {
+                x: c.x.rem_euclid(w),
+                y: c.y.rem_euclid(h),
+            },
+            None => c,
+        }
+    }
+
+    /// Changes the torus and starts the rope over, since knots that were
+    /// adjacent across the old seam can be any distance apart on the new
+    /// one.
+    fn set_wrap(&mut self, wrap: Option<(i32, i32)>) {
+        if wrap != self.wrap {
+            self.wrap = wrap;
+            self.reset();
+        }
+    }
+
+    /// The shortest way round the torus from one knot to another, so a knot
+    /// just across the seam still counts as adjacent.
+    fn wrapped_diff(&self, d: GridCoord) -> GridCoord {
+        match self.wrap {
+            Some((w, h)) => GridCoord {
+                x: (d.x + w / 2).rem_euclid(w) - w / 2,
+                y: (d.y + h / 2).rem_euclid(h) - h / 2,
+            },
+            None => d,
+        }
+    }
+
+    fn check_breakpoint(&mut self) {
+        let Some(target) = self.breakpoint else {
+            return;
+        };
+        if !self.breakpoint_hit && self.knots.contains(&target) {
+            self.breakpoint_hit = true;
+            self.paused = true;
+        }
+    }
+
+    fn breakpoint_ui(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.breakpoint.is_some();
+        if ui.checkbox(&mut enabled, "Break at").changed() {
+            self.breakpoint = enabled.then_some(GridCoord { x: 0, y: 0 });
+            self.breakpoint_hit = false;
+        }
+        if let Some(target) = self.breakpoint.as_mut() {
+            let x = ui.add(DragValue::new(&mut target.x).prefix("x: "));
+            let y = ui.add(DragValue::new(&mut target.y).prefix("y: "));
+            if x.changed() || y.changed() {
+                self.breakpoint_hit = false;
+            }
+            if self.breakpoint_hit {
+                ui.label("hit");
+            }
+        }
+    }
+
+    /// Adds each knot's position to its trail, dropping the oldest ones
+    /// past `trail_len`.
+    fn record_trails(&mut self) {
+        if !self.show_trails {
+            return;
+        }
+        for (trail, &knot) in self.trails.iter_mut().zip(&self.knots) {
+            trail.push_back(knot);
+            while trail.len() > self.trail_len {
+                trail.pop_front();
+            }
+        }
+    }
+
+    /// Advances by one move, or with `StepMode::FullInstruction` by every
+    /// move left in the current instruction.
     fn step(&mut self) {
-        let Some(inst) = self.instructions.front_mut() else { return; };
-        self.knots[0] += inst.dir.delta();
+        match self.step_mode {
+            StepMode::SubMove => self.step_move(),
+            StepMode::FullInstruction => {
+                let (remaining, hit) = (self.instructions.len(), self.breakpoint_hit);
+                while !self.instructions.is_empty() && self.instructions.len() == remaining {
+                    self.step_move();
+                    // Stop partway through the instruction on a breakpoint,
+                    // whether it paused a run or the rope was being stepped.
+                    if self.breakpoint_hit && !hit {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the head one cell and lets the rest of the rope follow.
+    fn step_move(&mut self) {
+        let Some(inst) = self.instructions.front_mut() else {
+            return;
+        };
+        let delta = inst.dir.delta();
+        inst.dist -= 1;
+        if inst.dist == 0 {
+            self.instructions.pop_front();
+        }
+        self.steps += 1;
+
+        self.knots[0] = self.wrapped(self.knots[0] + delta);
 
         for i in 1..self.knots.len() {
-            let diff = self.knots[i - 1] - self.knots[i];
+            let diff = self.wrapped_diff(self.knots[i - 1] - self.knots[i]);
             let (dx, dy) = match (diff.x, diff.y) {
                 (0, 0) => (0, 0),
                 (0, 1) | (1, 0) | (0, -1) | (-1, 0) => (0, 0),
@@ -170,21 +706,57 @@ impl Simulation {
                 _ => panic!("Should never happen: {diff:?}"),
             };
 
-            self.knots[i].x += dx;
-            self.knots[i].y += dy;
-            if i == self.knots.len() - 1 {
-                self.tail_visited.insert(self.knots[i]);
-            }
+            self.knots[i] = self.wrapped(self.knots[i] + GridCoord { x: dx, y: dy });
         }
-        inst.dist -= 1;
-        if inst.dist == 0 {
-            self.instructions.pop_front();
+        self.check_breakpoint();
+        self.record_trails();
+
+        let tail = self.knots[self.tail];
+        match &mut self.packed_visited {
+            Some(packed) => {
+                packed.insert(tail.packed());
+            }
+            None => {
+                self.tail_visited.entry(tail).or_insert(self.steps);
+            }
         }
     }
 }
 
+impl Steppable for Simulation {
+    fn step(&mut self) {
+        Simulation::step(self);
+    }
+
+    fn is_done(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Starts the rope over but keeps the camera and the settings,
+    /// including the wrap, which isn't part of the input.
+    fn reset(&mut self) {
+        *self = Self {
+            tail: self.tail,
+            fade: self.fade,
+            step_mode: self.step_mode,
+            speed: self.speed,
+            show_rate: self.show_rate,
+            show_sidebar: self.show_sidebar,
+            show_palette: self.show_palette,
+            view: self.view,
+            wrap: self.wrap,
+            breakpoint: self.breakpoint,
+            show_trails: self.show_trails,
+            trail_len: self.trail_len,
+            ..Self::with_sample(self.palette, self.sample)
+        };
+    }
+}
+
 impl eframe::App for Simulation {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        steppable::handle_keys(ctx, self);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.style_mut().spacing.interact_size.y *= 1.4;
@@ -194,38 +766,106 @@ impl eframe::App for Simulation {
                     .unwrap()
                     .size *= 1.4;
 
-                if ui.button("Reset").clicked() {
-                    *self = Self::new();
-                }
-                if ui.button("Step").clicked() {
-                    self.step = true;
-                }
+                steppable::controls(ui, self);
 
                 let paused = self.paused;
                 ui.toggle_value(&mut self.paused, if paused { "▶" } else { "⏸" });
 
                 ui.toggle_value(&mut self.show_sidebar, "Sidebar");
+                ui.toggle_value(&mut self.show_palette, "Palette");
+                ui.toggle_value(&mut self.fade, "Fade");
+
+                ui.separator();
+                ui.label("Step by:");
+                ui.selectable_value(&mut self.step_mode, StepMode::SubMove, "Move");
+                ui.selectable_value(
+                    &mut self.step_mode,
+                    StepMode::FullInstruction,
+                    "Instruction",
+                );
+
+                ui.separator();
+                if ui.button("Fit").clicked() {
+                    self.fit_requested = true;
+                }
+                if ui.button("Reset view").clicked() {
+                    self.view = default_view();
+                }
+                if ui.button("Export PNG").clicked() {
+                    if let Some(img) = self.visited_image() {
+                        if let Err(e) = img.save(EXPORT_FILE) {
+                            eprintln!("error: {e}");
+                        }
+                    }
+                }
+                if ui.button("Save").clicked() {
+                    if let Err(e) = self.save_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
+                }
+                if ui.button("Load").clicked() {
+                    if let Err(e) = self.load_state(Path::new(STATE_FILE)) {
+                        eprintln!("error: {e:#}");
+                    }
+                }
+
+                ui.separator();
+                let mut wrap = self.wrap;
+                let mut enabled = wrap.is_some();
+                if ui.checkbox(&mut enabled, "Wrap").changed() {
+                    wrap = enabled.then_some((40, 40));
+                }
+                if let Some((w, h)) = wrap.as_mut() {
+                    ui.add(DragValue::new(w).clamp_range(3..=1000).prefix("w: "));
+                    ui.add(DragValue::new(h).clamp_range(3..=1000).prefix("h: "));
+                }
+                self.set_wrap(wrap);
             });
 
             ui.horizontal(|ui| {
                 ui.label("Speed: ");
                 ui.add(Slider::new(&mut self.speed, 1..=20).prefix("x"));
+                ui.toggle_value(&mut self.show_rate, "Rate");
+
+                ui.separator();
+                if ui.checkbox(&mut self.show_trails, "Trails").changed() {
+                    self.trails = Default::default();
+                }
+                if self.show_trails {
+                    ui.add(Slider::new(&mut self.trail_len, 2..=200).text("steps"));
+                }
+
+                ui.separator();
+                self.breakpoint_ui(ui);
             });
         });
 
-        if self.step {
-            self.step();
-            self.step = false;
-        } else if !self.paused {
-            (0..self.speed).for_each(|_| {
+        egui::Window::new("Palette")
+            .open(&mut self.show_palette)
+            .show(ctx, |ui| self.palette.ui(ui));
+
+        // Counted in moves, since stepping by instruction makes several.
+        let moves_before = self.steps;
+        if !self.paused {
+            // A breakpoint can pause partway through the frame's steps.
+            for _ in 0..self.speed {
+                if self.paused {
+                    break;
+                }
                 self.step();
-            });
+            }
             ctx.request_repaint_after(Duration::from_millis(25));
         }
+        let steps = (self.steps - moves_before) as usize;
+        steppable::rate_overlay(ctx, &mut self.rate, steps, self.show_rate);
 
         if self.show_sidebar {
             egui::SidePanel::right("side_panel").show(ctx, |ui| {
                 ui.label(format!("{} places visited", self.tail_visited.len()));
+                if ui.button("Copy remaining input").clicked() {
+                    let input = self.to_input();
+                    ui.output_mut(|o| o.copied_text = input);
+                }
                 egui::ScrollArea::new([false, true]).show(ui, |ui| {
                     let mut it = self.instructions.iter();
                     for (i, ins) in it.by_ref().enumerate() {
@@ -261,38 +901,72 @@ impl eframe::App for Simulation {
                 painter_size = egui::vec2(500.0, 500.0);
             }
 
-            const SIDE: f32 = 5.0;
-
             let (res, painter) = ui.allocate_painter(painter_size, Sense::drag());
+            painter.rect_filled(res.rect, 0.0_f32, self.palette.air);
+
+            if std::mem::take(&mut self.fit_requested) {
+                self.fit_view(res.rect.size());
+            }
 
             let scroll_delta = ui.input(|i| i.scroll_delta);
             if scroll_delta != Vec2::ZERO {
-                self.zoom_level += scroll_delta.y * 0.0005;
+                self.view.zoom += scroll_delta.y * 0.0005;
             }
 
             if res.dragged_by(egui::PointerButton::Primary) {
                 res.clone().on_hover_cursor(egui::CursorIcon::Grabbing);
-                self.view_origin += res.drag_delta();
+                self.view.origin += res.drag_delta();
             }
 
-            let center = res.rect.center().to_vec2();
-            let zoom_clamped = self.zoom_level.clamp(0.1, 10.0);
-            let zoom_vec = Vec2::new(zoom_clamped, zoom_clamped);
+            let view = self.panel_view(res.rect);
+            let to_panel_pos = |pos: GridCoord| view.world_to_screen(pos.into());
 
-            let to_panel_pos = |pos: GridCoord| {
-                ((Vec2::new(pos.x as f32 * SIDE, pos.y as f32 * SIDE) * zoom_vec)
-                    + center
-                    + self.view_origin)
-                    .to_pos2()
-            };
+            if let Some((w, h)) = self.wrap {
+                let half_cell = view.side() / 2.0;
+                let rect = Rect::from_min_max(
+                    to_panel_pos(GridCoord { x: 0, y: 0 }) - half_cell,
+                    to_panel_pos(GridCoord { x: w, y: h }) - half_cell,
+                );
+                painter.rect_stroke(
+                    rect,
+                    Rounding::none(),
+                    Stroke::new(1.0_f32, self.palette.arrow),
+                );
+            }
 
-            self.tail_visited.iter().for_each(|coord| {
+            self.tail_visited.iter().for_each(|(coord, &visited_at)| {
                 let dot_pos = to_panel_pos(*coord);
-                painter.circle_stroke(dot_pos, 2.0, Stroke::new(2.0, Color32::DARK_RED));
+                painter.circle_stroke(
+                    dot_pos,
+                    2.0,
+                    Stroke::new(2.0_f32, self.visited_color(visited_at)),
+                );
             });
 
             let num_knots = self.knots.len();
 
+            if self.show_trails {
+                for (i, trail) in self.trails.iter().enumerate() {
+                    let t = (num_knots - i) as f32 / num_knots as f32;
+                    let color = self.palette.knot.gamma_multiply(0.25 + 0.75 * t);
+                    let newest_first = trail.iter().rev();
+                    for (age, (to, from)) in
+                        newest_first.clone().zip(newest_first.skip(1)).enumerate()
+                    {
+                        // A jump across the wrapped edge isn't drawn.
+                        let d = *to - *from;
+                        if d.x.abs() > 1 || d.y.abs() > 1 {
+                            continue;
+                        }
+                        let fade = 1.0 - age as f32 / trail.len() as f32;
+                        painter.line_segment(
+                            [to_panel_pos(*from), to_panel_pos(*to)],
+                            Stroke::new(1.5_f32, color.gamma_multiply(fade)),
+                        );
+                    }
+                }
+            }
+
             for (i, knot_pos) in self.knots.iter().copied().enumerate() {
                 let knot_pos = to_panel_pos(knot_pos);
                 if i > 0 {
@@ -300,30 +974,107 @@ impl eframe::App for Simulation {
                     painter.arrow(
                         prev_pos,
                         knot_pos - prev_pos,
-                        Stroke::new(1.0, Color32::YELLOW),
+                        Stroke::new(1.0_f32, self.palette.arrow),
                     )
                 }
             }
 
             for (i, knot_pos) in self.knots.iter().copied().enumerate() {
                 let knot_pos = to_panel_pos(knot_pos);
+                // The head gets the full knot color and each knot after it
+                // fades towards a quarter of it.
+                let t = (num_knots - i) as f32 / num_knots as f32;
+                let shade = |c: u8| (c as f32 * (0.25 + 0.75 * t)) as u8;
+                let [r, g, b, _] = self.palette.knot.to_array();
                 painter.circle_filled(
                     knot_pos,
                     2.0,
-                    Color32::from_rgb(
-                        20,
-                        60 + ((255.0 - 60.0) * (num_knots as f32 - i as f32) / num_knots as f32)
-                            as u8,
-                        20,
-                    ),
+                    Color32::from_rgb(shade(r), shade(g), shade(b)),
+                );
+            }
+
+            if let Some(pos) = res.hover_pos() {
+                let cell = GridCoord::from(view.screen_to_world(pos));
+                let galley = painter.layout_no_wrap(
+                    self.hover_text(cell),
+                    FontId::proportional(14.0),
+                    self.palette.arrow,
                 );
+                let pad = Vec2::splat(4.0);
+                let text_pos = res.rect.left_top() + 2.0 * pad;
+                let bg = Rect::from_min_size(text_pos, galley.size()).expand2(pad);
+                painter.rect_filled(bg, 2.0, self.palette.air);
+                painter.galley(text_pos, galley);
             }
         });
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.palette.save(storage);
+    }
+}
+
+pub(crate) fn app(palette: Palette) -> Box<dyn eframe::App> {
+    Box::new(Simulation::new(palette))
+}
+
+/// The visited cells and knots after the whole input, one pixel per cell.
+pub(crate) fn thumbnail() -> ColorImage {
+    let mut sim = Simulation::new(Palette::default());
+    steppable::run_to_completion(&mut sim);
+    let bounds = bounds(sim.tail_visited.keys().chain(&sim.knots).copied())
+        .expect("the rope is always somewhere");
+    let img = sim.render(bounds, true);
+    ColorImage::from_rgba_unmultiplied([img.width() as _, img.height() as _], &img)
+}
+
+fn count_tail_visits(instructions: &str, knots: usize, packed: bool) -> Result<usize> {
+    if !(1..=10).contains(&knots) {
+        bail!("the rope has 1 to 10 knots, not {knots}");
+    }
+    let mut sim = Simulation {
+        tail: knots - 1,
+        packed_visited: packed.then(HashSet::default),
+        ..Simulation::with_instructions(Palette::default(), parse_instructions(instructions)?)
+    };
+    while !sim.is_done() {
+        sim.step();
+    }
+    Ok(match &sim.packed_visited {
+        Some(packed) => packed.len(),
+        None => sim.tail_visited.len(),
+    })
+}
+
+/// How many cells the last of `knots` knots visits, running the same
+/// steps as the window without one.
+pub fn tail_visits(instructions: &str, knots: usize) -> Result<usize> {
+    count_tail_visits(instructions, knots, false)
+}
+
+/// The same count as `tail_visits`, keeping each cell as a packed `u64`
+/// rather than a coordinate with the step it was reached on.
+pub fn tail_visits_packed(instructions: &str, knots: usize) -> Result<usize> {
+    count_tail_visits(instructions, knots, true)
+}
+
+pub fn solve_part1(input: &str) -> Result<Answer> {
+    Ok(tail_visits(input, 2)?.into())
+}
+
+pub fn solve_part2(input: &str) -> Result<Answer> {
+    Ok(tail_visits(input, 10)?.into())
+}
+
+pub(crate) fn describe() -> DayInfo {
+    DayInfo {
+        title: "Rope Bridge: drags a rope of knots around and tracks where its tail goes",
+        answers: [AnswerKind::Int, AnswerKind::Int],
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
-fn main() {
+pub fn main() -> Result<()> {
     console_error_panic_hook::set_once();
     tracing_wasm::set_as_global_default();
 
@@ -334,15 +1085,61 @@ fn main() {
             .start(
                 "canvas",
                 web_options,
-                Box::new(|_cc| Box::new(Simulation::new())),
+                Box::new(|cc| app(Palette::load(cc.storage))),
             )
             .await
             .expect("eframe failed to start");
     });
+
+    Ok(())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn main() {
+pub fn main() -> Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.iter().any(|arg| arg == "--summary") {
+        let instructions = parse_instructions(Day::D9.input_for(&args))?;
+        print!("{}", movement_summary(&instructions));
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--count") {
+        let count = if args.iter().any(|arg| arg == "--packed") {
+            tail_visits_packed
+        } else {
+            tail_visits
+        };
+        let input = Day::D9.input_for(&args);
+        println!("Part 1: {}", count(input, 2)?);
+        println!("Part 2: {}", count(input, 10)?);
+        return Ok(());
+    }
+
+    if args.iter().any(|arg| arg == "--bench") {
+        let mut sim = Simulation::with_sample(Palette::default(), wants_sample(&args));
+        println!("{}", steppable::bench(&mut sim));
+        println!("visited: {}", sim.tail_visited.len());
+        return Ok(());
+    }
+
+    if let Some(i) = args.iter().position(|arg| arg == "--dump-frames") {
+        let Some(dir) = args.get(i + 1) else {
+            bail!("Usage: --dump-frames DIR [--every N]");
+        };
+        let every = match args.iter().position(|arg| arg == "--every") {
+            Some(i) => match args.get(i + 1) {
+                Some(n) => n.parse()?,
+                None => bail!("Usage: --dump-frames DIR [--every N]"),
+            },
+            None => 1,
+        };
+        let mut sim = Simulation::with_sample(Palette::default(), wants_sample(&args));
+        let frames = sim.dump_frames(Path::new(dir), every)?;
+        println!("Wrote {frames} frames to {dir}");
+        return Ok(());
+    }
+
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(1280.0, 720.0)),
         ..Default::default()
@@ -351,7 +1148,414 @@ fn main() {
     eframe::run_native(
         "Advent of Code 2022 - Day 9",
         options,
-        Box::new(|_cc| Box::new(Simulation::new())),
+        Box::new(move |cc| {
+            let palette = Palette::load(cc.storage);
+            Box::new(Simulation::with_sample(palette, wants_sample(&args)))
+        }),
     )
     .expect("eframe failed to start");
+
+    Ok(())
+}
+
+#[test]
+fn test_direction() {
+    for d in Direction::ALL {
+        assert_eq!(Direction::from_delta(d.delta()), Some(d));
+        assert_eq!(d.opposite().delta(), GridCoord { x: 0, y: 0 } - d.delta());
+        assert_eq!(d.turn_right().turn_left(), d);
+        assert_eq!(d.turn_right().turn_right().turn_right().turn_right(), d);
+    }
+
+    assert_eq!(Direction::Up.turn_right(), Direction::Right);
+    assert_eq!(Direction::Up.turn_left(), Direction::Left);
+    assert_eq!(Direction::from_delta(GridCoord { x: 1, y: 1 }), None);
+    assert_eq!(Direction::from_delta(GridCoord { x: 0, y: 0 }), None);
+}
+
+#[test]
+fn test_instruction_display() {
+    for line in ["U 4", "D 1", "L 12", "R 0"] {
+        let (_, inst) = all_consuming(Instruction::parse)(line).finish().unwrap();
+        assert_eq!(inst.to_string(), line);
+    }
+
+    let mut sim = Simulation::new(Palette::default());
+    sim.instructions = parse_instructions("R 4\nU 4\nL 3").unwrap();
+    assert_eq!(sim.to_input(), "R 4\nU 4\nL 3\n");
+    (0..5).for_each(|_| sim.step_move());
+    assert_eq!(sim.to_input(), "U 3\nL 3\n");
+    assert_eq!(
+        parse_instructions(&sim.to_input()).unwrap(),
+        sim.instructions
+    );
+
+    let skipped = parse_instructions("R 0\nL 2\nD 0").unwrap();
+    assert_eq!(
+        skipped
+            .iter()
+            .map(|inst| inst.to_string())
+            .collect::<Vec<_>>(),
+        ["L 2"]
+    );
+    assert!(parse_instructions("R 2\nL -1").is_err());
+}
+
+#[test]
+fn test_breakpoint() {
+    let mut sim = Simulation::new(Palette::default());
+    // The head passes (2, 0) on the way out and again on the way back.
+    sim.instructions = parse_instructions("R 4\nL 4").unwrap();
+    sim.breakpoint = Some(GridCoord { x: 2, y: 0 });
+
+    let mut pauses = vec![];
+    while !sim.is_done() {
+        sim.paused = false;
+        sim.step();
+        if sim.paused {
+            pauses.push(sim.steps);
+        }
+    }
+    assert_eq!(pauses, [2]);
+    assert!(sim.breakpoint_hit);
+
+    // Cleared, it pauses again the next time a knot gets there.
+    sim.breakpoint_hit = false;
+    sim.paused = false;
+    sim.instructions = parse_instructions("R 1").unwrap();
+    sim.step();
+    assert!(sim.paused);
+
+    // Stepping a whole instruction stops on the move that lands there.
+    let mut sim = Simulation {
+        step_mode: StepMode::FullInstruction,
+        breakpoint: Some(GridCoord { x: 2, y: 0 }),
+        paused: false,
+        ..Simulation::new(Palette::default())
+    };
+    sim.instructions = parse_instructions("R 4\nL 4").unwrap();
+    sim.step();
+    assert!(sim.paused);
+    assert_eq!(sim.knots[0], GridCoord { x: 2, y: 0 });
+    assert_eq!(sim.to_input(), "R 2\nL 4\n");
+    sim.step();
+    assert_eq!(sim.knots[0], GridCoord { x: 4, y: 0 });
+    assert_eq!(sim.to_input(), "L 4\n");
+}
+
+#[test]
+fn test_wrap() {
+    let mut sim = Simulation::new(Palette::default());
+    sim.instructions = parse_instructions("R 5\nU 12").unwrap();
+    sim.wrap = Some((5, 5));
+
+    for _ in 0..5 {
+        sim.step();
+    }
+    assert_eq!(sim.knots[0], GridCoord { x: 0, y: 0 });
+
+    while !sim.instructions.is_empty() {
+        sim.step();
+    }
+    assert_eq!(sim.knots[0], GridCoord { x: 0, y: 3 });
+    assert!(sim
+        .knots
+        .iter()
+        .chain(sim.tail_visited.keys())
+        .all(|k| (0..5).contains(&k.x) && (0..5).contains(&k.y)));
+}
+
+#[test]
+fn test_set_wrap() {
+    let mut sim = Simulation::new(Palette::default());
+    sim.set_wrap(Some((40, 40)));
+    // Leaves the tail at x = 0 and the knot ahead of it at x = 39, which
+    // are only adjacent across the seam.
+    sim.instructions = parse_instructions("L 9\nR 5").unwrap();
+    (0..9).for_each(|_| sim.step_move());
+    assert_eq!(sim.knots[8].x - sim.knots[9].x, 39);
+
+    for wrap in [Some((50, 40)), None, Some((40, 40))] {
+        sim.set_wrap(wrap);
+        assert_eq!(sim.wrap, wrap);
+        assert_eq!(sim.steps, 0);
+        sim.instructions = parse_instructions("L 9\nR 5").unwrap();
+        steppable::run_to_completion(&mut sim);
+    }
+
+    // Setting the same wrap again keeps the run going.
+    let steps = sim.steps;
+    sim.set_wrap(Some((40, 40)));
+    assert_eq!(sim.steps, steps);
+}
+
+#[test]
+fn test_save_state() {
+    let mut sim = Simulation::new(Palette::light());
+    sim.instructions = parse_instructions("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2").unwrap();
+    (0..7).for_each(|_| sim.step());
+
+    let path = std::env::temp_dir().join(format!("day_9_state_{}.json", std::process::id()));
+    sim.save_state(&path).unwrap();
+    let mut loaded = Simulation::new(Palette::dark());
+    loaded.load_state(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.knots, sim.knots);
+    assert_eq!(loaded.tail_visited, sim.tail_visited);
+    assert_eq!(loaded.instructions, sim.instructions);
+    assert_eq!(
+        loaded.instructions.front(),
+        Some(&Instruction {
+            dir: Direction::Up,
+            dist: 1
+        })
+    );
+    assert_eq!(loaded.palette, Palette::dark());
+}
+
+#[test]
+fn test_steppable() {
+    let mut sim = Simulation::new(Palette::default());
+    sim.instructions = parse_instructions("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20").unwrap();
+    assert!(!sim.is_done());
+
+    let steps = steppable::run_to_completion(&mut sim);
+    assert!(sim.is_done());
+    assert_eq!(steps, 5 + 8 + 8 + 3 + 17 + 10 + 25 + 20);
+    assert_eq!(sim.tail_visited.len(), 36);
+}
+
+#[test]
+fn test_visited_bounds() {
+    let mut sim = Simulation::new(Palette::default());
+    assert_eq!(sim.visited_bounds(), None);
+    assert!(sim.visited_image().is_none());
+
+    sim.tail_visited = [(0, 0), (-3, 2), (4, -1), (1, 5)]
+        .map(|(x, y)| (GridCoord { x, y }, 0))
+        .into_iter()
+        .collect();
+    assert_eq!(
+        sim.visited_bounds(),
+        Some((GridCoord { x: -3, y: -1 }, GridCoord { x: 4, y: 5 }))
+    );
+
+    let img = sim.visited_image().unwrap();
+    assert_eq!(img.dimensions(), (8, 7));
+    assert_eq!(img.get_pixel(0, 3).0, sim.palette.visited.to_array());
+    assert_eq!(img.get_pixel(0, 0).0, sim.palette.air.to_array());
+
+    sim.fit_view(Vec2::new(400.0, 350.0));
+    assert_eq!(sim.view.zoom, 10.0_f32.min(350.0 / 35.0));
+    assert_eq!(
+        sim.view.origin,
+        Vec2::new(-0.5, -2.0) * SIDE * sim.view.zoom
+    );
+}
+
+#[test]
+fn test_dump_frames() {
+    let dir = std::env::temp_dir().join(format!("day_9_frames_{}", std::process::id()));
+    let frames = |every| {
+        let mut sim = Simulation::new(Palette::default());
+        sim.instructions = parse_instructions("R 3\nU 2\nL 1").unwrap();
+        let written = sim.dump_frames(&dir, every).unwrap();
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        let dims = files
+            .iter()
+            .map(|f| image::open(dir.join(f)).unwrap().to_rgba8().dimensions())
+            .collect::<std::collections::HashSet<_>>();
+        std::fs::remove_dir_all(&dir).unwrap();
+        (written, files, dims)
+    };
+
+    let (written, files, dims) = frames(1);
+    assert_eq!(written, 6);
+    assert_eq!(files.len(), 6);
+    assert_eq!(files[0], "frame_00001.png");
+    assert_eq!(files[5], "frame_00006.png");
+    // The head ends up 2 right and 2 up of the start.
+    assert_eq!(dims, std::collections::HashSet::from([(4, 3)]));
+
+    assert_eq!(frames(4).0, 1);
+    assert!(Simulation::new(Palette::default())
+        .dump_frames(&dir, 0)
+        .is_err());
+}
+
+#[test]
+fn test_visit_steps() {
+    let mut sim = Simulation::new(Palette::default());
+    sim.instructions = parse_instructions("R 4\nL 4\nR 4").unwrap();
+
+    let mut first_visits = HashMap::default();
+    while !sim.is_done() {
+        sim.step();
+        first_visits.entry(sim.knots[9]).or_insert(sim.steps);
+    }
+    assert_eq!(sim.steps, 12);
+    // The head never gets far enough to pull the tail off the origin.
+    assert_eq!(sim.tail_visited, first_visits);
+    assert_eq!(sim.tail_visited[&GridCoord { x: 0, y: 0 }], 1);
+
+    let mut sim = Simulation::new(Palette::default());
+    sim.instructions = parse_instructions("R 12\nL 12").unwrap();
+    steppable::run_to_completion(&mut sim);
+    // The tail trails the head by nine, so it reaches x = 3 on step 12
+    // and passes back over it later without changing the record.
+    assert_eq!(sim.tail_visited[&GridCoord { x: 3, y: 0 }], 12);
+    assert_eq!(sim.tail_visited[&GridCoord { x: 1, y: 0 }], 10);
+
+    sim.steps = 12 + FADE_STEPS;
+    assert_eq!(sim.visited_color(sim.steps), sim.palette.visited);
+    assert_eq!(
+        sim.visited_color(12),
+        sim.palette.visited.gamma_multiply(0.35)
+    );
+    sim.fade = false;
+    assert_eq!(sim.visited_color(12), sim.palette.visited);
+}
+
+#[test]
+fn test_reset_keeps_view() {
+    let mut sim = Simulation::new(Palette::default());
+    sim.view = ViewTransform {
+        origin: Vec2::new(12.0, -40.0),
+        zoom: 3.5,
+        ..default_view()
+    };
+    sim.fade = false;
+    sim.wrap = Some((20, 20));
+    for _ in 0..50 {
+        sim.step();
+    }
+    let view = sim.view;
+
+    sim.reset();
+    let fresh = Simulation::new(Palette::default());
+    assert_eq!(sim.instructions, fresh.instructions);
+    assert_eq!(sim.knots, fresh.knots);
+    assert_eq!(sim.tail_visited, fresh.tail_visited);
+    assert_eq!(sim.steps, 0);
+    assert_eq!(sim.view, view);
+    assert!(!sim.fade);
+    assert_eq!(sim.wrap, Some((20, 20)));
+}
+
+#[test]
+fn test_panel_view() {
+    let mut sim = Simulation::new(Palette::default());
+    let rect = Rect::from_min_size(egui::pos2(40.0, 20.0), egui::vec2(800.0, 600.0));
+    for zoom in [0.5, 1.0, 2.5] {
+        sim.view.zoom = zoom;
+        let view = sim.panel_view(rect);
+        assert_eq!(view.screen_to_world(rect.center()), Coord { x: 0, y: 0 });
+    }
+
+    sim.view.zoom = 2.0;
+    sim.view.origin = Vec2::new(-3.0, 1.0) * SIDE * 2.0;
+    let view = sim.panel_view(rect);
+    assert_eq!(view.screen_to_world(rect.center()), Coord { x: 3, y: -1 });
+
+    sim.tail_visited.insert(GridCoord { x: 3, y: -1 }, 0);
+    let hovered = GridCoord::from(view.screen_to_world(rect.center()));
+    assert_eq!(sim.hover_text(hovered), "hovering (3, -1) — visited: yes");
+    assert_eq!(
+        sim.hover_text(GridCoord { x: 0, y: 0 }),
+        "hovering (0, 0) — visited: no"
+    );
+}
+
+#[test]
+fn test_step_modes() {
+    let run = |step_mode| {
+        let mut sim = Simulation {
+            instructions: parse_instructions(include_str!("test_files/day_9.txt")).unwrap(),
+            step_mode,
+            ..Simulation::new(Palette::default())
+        };
+        let lines = sim.instructions.len();
+        let calls = steppable::run_to_completion(&mut sim);
+        (sim, lines, calls)
+    };
+
+    let (by_move, _, move_calls) = run(StepMode::SubMove);
+    let (by_instruction, lines, instruction_calls) = run(StepMode::FullInstruction);
+    assert_eq!(instruction_calls, lines);
+    assert!(move_calls > instruction_calls);
+    assert_eq!(by_instruction.steps, by_move.steps);
+    assert_eq!(by_instruction.knots, by_move.knots);
+    assert_eq!(by_instruction.tail_visited, by_move.tail_visited);
+}
+
+#[test]
+fn test_packed_visits() {
+    let coords = [(0, 0), (-1, 0), (0, -1), (i32::MIN, i32::MAX), (7, -7)];
+    let keys = coords
+        .map(|(x, y)| GridCoord { x, y }.packed())
+        .into_iter()
+        .collect::<HashSet<_>>();
+    assert_eq!(keys.len(), coords.len());
+
+    for input in [Day::D9.sample(), Day::D9.input()] {
+        for knots in [2, 10] {
+            assert_eq!(
+                tail_visits_packed(input, knots).unwrap(),
+                tail_visits(input, knots).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_movement_summary() {
+    let instructions = parse_instructions("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2").unwrap();
+    let summary = movement_summary(&instructions);
+    // Up, Right, Down, Left.
+    assert_eq!(summary.steps, [4, 10, 2, 8]);
+    assert_eq!(summary.net, GridCoord { x: 2, y: -2 });
+
+    let mut sim = Simulation {
+        instructions: instructions.clone(),
+        ..Simulation::new(Palette::default())
+    };
+    steppable::run_to_completion(&mut sim);
+    assert_eq!(sim.knots[0], summary.net);
+    assert_eq!(
+        summary.to_string(),
+        "Up: 4\nRight: 10\nDown: 2\nLeft: 8\nNet displacement: 2, -2\n"
+    );
+}
+
+#[test]
+fn test_trails() {
+    let mut sim = Simulation {
+        show_trails: true,
+        trail_len: 3,
+        ..Simulation::new(Palette::default())
+    };
+    sim.instructions = parse_instructions("R 4\nU 2").unwrap();
+    (0..2).for_each(|_| sim.step_move());
+    assert_eq!(
+        sim.trails[0],
+        [(1, 0), (2, 0)].map(|(x, y)| GridCoord { x, y })
+    );
+
+    (0..4).for_each(|_| sim.step_move());
+    assert_eq!(
+        sim.trails[0],
+        [(4, 0), (4, -1), (4, -2)].map(|(x, y)| GridCoord { x, y })
+    );
+    assert!(sim.trails.iter().all(|t| t.len() == 3));
+    assert_eq!(sim.trails[9].back(), Some(&sim.knots[9]));
+
+    // Not kept at all while hidden.
+    let mut sim = Simulation::new(Palette::default());
+    sim.step_move();
+    assert!(sim.trails.iter().all(|t| t.is_empty()));
 }
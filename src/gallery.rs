@@ -0,0 +1,136 @@
+use anyhow::Result;
+use egui::{ColorImage, TextureHandle, TextureOptions, Vec2};
+
+use crate::{day_10, day_12, day_14, day_8, day_9, palette::Palette};
+
+/// The longest side of a thumbnail on screen.
+const THUMBNAIL_SIZE: f32 = 220.0;
+
+type OpenFn = fn(Palette) -> Box<dyn eframe::App>;
+
+struct Tile {
+    title: &'static str,
+    image: ColorImage,
+    texture: Option<TextureHandle>,
+    /// Opens the interactive version, for the days that have one.
+    open: Option<OpenFn>,
+}
+
+/// Every visual day solved up front and shown as a thumbnail. Clicking one
+/// opens its simulation in the same window.
+struct Gallery {
+    tiles: Vec<Tile>,
+    palette: Palette,
+    open: Option<(&'static str, Box<dyn eframe::App>)>,
+}
+
+impl Gallery {
+    fn new(palette: Palette) -> Self {
+        let tiles = [
+            ("Day 8", day_8::thumbnail(), None),
+            ("Day 9", day_9::thumbnail(), Some(day_9::app as OpenFn)),
+            ("Day 10", day_10::thumbnail(), None),
+            ("Day 12", day_12::thumbnail(), Some(day_12::app as OpenFn)),
+            ("Day 14", day_14::thumbnail(), Some(day_14::app as OpenFn)),
+        ];
+        Self {
+            tiles: tiles
+                .into_iter()
+                .map(|(title, image, open)| Tile {
+                    title,
+                    image,
+                    texture: None,
+                    open,
+                })
+                .collect(),
+            palette,
+            open: None,
+        }
+    }
+}
+
+/// Scales `size` up or down so its longer side is `THUMBNAIL_SIZE`.
+fn thumbnail_size(size: [usize; 2]) -> Vec2 {
+    let size = Vec2::new(size[0] as f32, size[1] as f32);
+    size * (THUMBNAIL_SIZE / size.max_elem())
+}
+
+impl eframe::App for Gallery {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some((title, app)) = &mut self.open {
+            let mut back = false;
+            egui::TopBottomPanel::top("gallery_panel").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    back = ui.button("⬅ Gallery").clicked();
+                    ui.heading(*title);
+                });
+            });
+            app.update(ctx, frame);
+            if back {
+                self.open = None;
+            }
+            return;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for tile in &mut self.tiles {
+                        let texture = tile.texture.get_or_insert_with(|| {
+                            ui.ctx().load_texture(
+                                tile.title,
+                                tile.image.clone(),
+                                TextureOptions::NEAREST,
+                            )
+                        });
+                        ui.vertical(|ui| {
+                            ui.label(tile.title);
+                            let size = thumbnail_size(tile.image.size);
+                            let button = ui.add(egui::ImageButton::new(texture.id(), size));
+                            if let Some(open) = tile.open {
+                                if button.on_hover_text("Open the simulation").clicked() {
+                                    self.open = Some((tile.title, open(self.palette)));
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+        });
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.palette.save(storage);
+    }
+}
+
+pub fn main() -> Result<()> {
+    let options = eframe::NativeOptions {
+        initial_window_size: Some(egui::vec2(1280.0, 720.0)),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Advent of Code 2022",
+        options,
+        Box::new(|cc| Box::new(Gallery::new(Palette::load(cc.storage)))),
+    )
+    .expect("eframe failed to start");
+    Ok(())
+}
+
+#[test]
+fn test_thumbnails() {
+    let sizes = [
+        (day_8::thumbnail(), [99, 99]),
+        (day_9::thumbnail(), [126, 345]),
+        (day_10::thumbnail(), [40, 6]),
+        (day_12::thumbnail(), [64, 41]),
+        (day_14::thumbnail(), [401, 180]),
+    ];
+    for (image, size) in sizes {
+        assert_eq!(image.size, size);
+        assert_eq!(image.pixels.len(), size[0] * size[1]);
+        assert!(image.pixels.iter().any(|&p| p != image.pixels[0]));
+    }
+}
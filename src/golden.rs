@@ -0,0 +1,40 @@
+use std::{fs, path::PathBuf};
+
+/// Set to regenerate the golden files instead of comparing against them,
+/// e.g. `UPDATE_GOLDENS=1 cargo test`.
+pub const UPDATE_VAR: &str = "UPDATE_GOLDENS";
+
+fn golden_path(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "src",
+        "test_files",
+        "expected",
+        name,
+    ]
+    .iter()
+    .collect()
+}
+
+/// Asserts that `actual` matches `test_files/expected/<name>` byte for byte,
+/// or rewrites the file when `UPDATE_GOLDENS` is set.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = golden_path(name);
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "can't read {}: {e}; run with {UPDATE_VAR}=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "{name} differs from its golden file; run with {UPDATE_VAR}=1 to accept the change\n\
+         --- expected\n{expected}\n--- actual\n{actual}"
+    );
+}
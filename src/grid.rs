@@ -0,0 +1,77 @@
+/// The size of a grid stored row by row, for looking up neighbours without
+/// stepping off the edge. Cells are `(x, y)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Orthogonal steps first, then diagonal ones.
+const DELTAS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+impl Bounds {
+    pub fn contains(self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    fn step(
+        self,
+        (x, y): (usize, usize),
+        deltas: &[(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        deltas.iter().filter_map(move |&(dx, dy)| {
+            let c = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            self.contains(c).then_some(c)
+        })
+    }
+
+    /// The cells left, right, above and below `c` that are on the grid.
+    pub fn neighbors4(self, c: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        self.step(c, &DELTAS[..4])
+    }
+
+    /// `neighbors4` followed by the diagonal cells on the grid.
+    pub fn neighbors8(self, c: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        self.step(c, &DELTAS)
+    }
+}
+
+#[test]
+fn test_neighbors() {
+    use std::collections::HashSet;
+
+    let bounds = Bounds {
+        width: 4,
+        height: 3,
+    };
+    let set = |it: &mut dyn Iterator<Item = (usize, usize)>| it.collect::<HashSet<_>>();
+
+    assert_eq!(
+        set(&mut bounds.neighbors4((0, 0))),
+        HashSet::from([(1, 0), (0, 1)])
+    );
+    assert_eq!(
+        set(&mut bounds.neighbors8((0, 0))),
+        HashSet::from([(1, 0), (0, 1), (1, 1)])
+    );
+    assert_eq!(
+        set(&mut bounds.neighbors4((3, 1))),
+        HashSet::from([(2, 1), (3, 0), (3, 2)])
+    );
+    assert_eq!(
+        set(&mut bounds.neighbors8((3, 1))),
+        HashSet::from([(2, 1), (3, 0), (3, 2), (2, 0), (2, 2)])
+    );
+    assert_eq!(bounds.neighbors4((1, 1)).count(), 4);
+    assert_eq!(bounds.neighbors8((1, 1)).count(), 8);
+    assert_eq!(bounds.neighbors8((3, 2)).count(), 3);
+}
@@ -0,0 +1,89 @@
+use nom::{combinator::all_consuming, Finish, IResult};
+
+/// How forgiving the line parsers are about what follows the part of a
+/// line they understand. Lenient by default.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Rejects a line with anything left over after the parser is done.
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    /// Strict with `--strict` on the command line.
+    pub fn from_args(args: &[String]) -> Self {
+        Self {
+            strict: args.iter().any(|arg| arg == "--strict"),
+        }
+    }
+
+    /// Runs `parser` on `line`, which in strict mode it has to consume
+    /// entirely. `None` if it doesn't match, so callers can say which line
+    /// was wrong.
+    pub fn parse_line<'a, O>(
+        self,
+        mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+        line: &'a str,
+    ) -> Option<O> {
+        let parsed = if self.strict {
+            all_consuming(&mut parser)(line)
+        } else {
+            parser(line)
+        };
+        parsed.finish().ok().map(|(_, out)| out)
+    }
+}
+
+/// `None` for empty or whitespace-only input, so solvers can return an empty
+/// answer up front instead of panicking on a missing first line.
+pub fn non_empty(input: &str) -> Option<&str> {
+    (!input.trim().is_empty()).then_some(input)
+}
+
+/// Whether a line holds puzzle data rather than being blank or a `#`
+/// comment.
+pub fn is_significant(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+/// The lines of `input` that hold puzzle data, each with its 1-based line
+/// number in the original input for error messages.
+pub fn significant_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| is_significant(line))
+}
+
+#[test]
+fn test_non_empty() {
+    assert_eq!(non_empty(""), None);
+    assert_eq!(non_empty(" \n\t\n"), None);
+    assert_eq!(non_empty("1\n"), Some("1\n"));
+}
+
+#[test]
+fn test_significant_lines() {
+    let input = "# header\nnoop\n\n  # indented\naddx 3\n   \n";
+    let lines = significant_lines(input).collect::<Vec<_>>();
+    assert_eq!(lines, [(2, "noop"), (5, "addx 3")]);
+    assert_eq!(significant_lines("").count(), 0);
+}
+
+#[test]
+fn test_parse_options() {
+    let number = nom::character::complete::u32::<_, nom::error::Error<_>>;
+    let lenient = ParseOptions::default();
+    let strict = ParseOptions { strict: true };
+
+    assert_eq!(lenient.parse_line(number, "42"), Some(42));
+    assert_eq!(strict.parse_line(number, "42"), Some(42));
+    assert_eq!(lenient.parse_line(number, "42 and more"), Some(42));
+    assert_eq!(strict.parse_line(number, "42 and more"), None);
+    assert_eq!(lenient.parse_line(number, "x42"), None);
+
+    let args = ["--sample", "--strict"].map(String::from);
+    assert_eq!(ParseOptions::from_args(&args), strict);
+    assert_eq!(ParseOptions::from_args(&[]), lenient);
+}
@@ -0,0 +1,36 @@
+pub mod answer;
+pub mod budget;
+pub mod cache;
+pub mod coord;
+pub mod day;
+pub mod day_1;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_2;
+pub mod day_3;
+pub mod day_4;
+pub mod day_5;
+pub mod day_6;
+pub mod day_7;
+pub mod day_8;
+pub mod day_9;
+pub mod gallery;
+pub mod golden;
+pub mod grid;
+pub mod input;
+pub mod manifest;
+pub mod palette;
+pub mod part;
+pub mod search;
+pub mod state;
+pub mod steppable;
+pub mod util;
+pub mod view;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use day::{Solver, SOLVERS};
@@ -0,0 +1,166 @@
+use std::{
+    fmt::Write,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+use crate::{answer::Answer, day::Day};
+
+/// One part of one day, solved against a bundled input.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub day: Day,
+    pub part: u8,
+    /// The answer, or why the day couldn't give one.
+    pub answer: Result<Answer, String>,
+    pub elapsed: Duration,
+}
+
+/// How `manifest --sort` orders the entries.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    Day,
+    /// Slowest first.
+    Time,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "day" => Ok(SortBy::Day),
+            "time" => Ok(SortBy::Time),
+            _ => bail!("unknown sort {s:?}, expected day or time"),
+        }
+    }
+}
+
+/// Solves both parts of every day in `days`, with the input `input` picks
+/// for each.
+pub fn build(days: &[Day], input: fn(Day) -> &'static str) -> Vec<Entry> {
+    days.iter()
+        .flat_map(|&day| [1, 2].map(|part| (day, part)))
+        .map(|(day, part)| {
+            let start = Instant::now();
+            let answer = day.run(part, input(day)).map_err(|e| e.to_string());
+            Entry {
+                day,
+                part,
+                answer,
+                elapsed: start.elapsed(),
+            }
+        })
+        .collect()
+}
+
+pub fn sort(entries: &mut [Entry], by: SortBy) {
+    match by {
+        SortBy::Day => entries.sort_by_key(|e| (e.day, e.part)),
+        SortBy::Time => entries.sort_by_key(|e| std::cmp::Reverse(e.elapsed)),
+    }
+}
+
+/// The answer as one table cell; multi-line pictures are joined with `|`.
+fn cell(answer: &Result<Answer, String>) -> String {
+    match answer {
+        Ok(answer) => answer
+            .to_string()
+            .trim_end()
+            .lines()
+            .collect::<Vec<_>>()
+            .join("|"),
+        Err(e) => format!("error: {e}"),
+    }
+}
+
+/// The entries as a plain text table with aligned columns.
+pub fn to_table(entries: &[Entry]) -> String {
+    let rows = entries
+        .iter()
+        .map(|e| {
+            [
+                e.day.number().to_string(),
+                e.part.to_string(),
+                cell(&e.answer),
+                format!("{:.2?}", e.elapsed),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = ["Day", "Part", "Answer", "Time"].map(String::from);
+
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(value, width)| format!("{value:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    out
+}
+
+/// The entries as a JSON array. Number answers stay numbers, and a day
+/// without an answer has an `error` instead.
+pub fn to_json(entries: &[Entry]) -> String {
+    let entries = entries
+        .iter()
+        .map(|e| {
+            let mut value = json!({
+                "day": e.day.number(),
+                "part": e.part,
+                "elapsed_ms": e.elapsed.as_secs_f64() * 1000.0,
+            });
+            let (key, answer) = match &e.answer {
+                Ok(Answer::Int(n)) => ("answer", Value::from(*n)),
+                Ok(Answer::Text(s)) => ("answer", Value::from(s.as_str())),
+                Err(e) => ("error", Value::from(e.as_str())),
+            };
+            value[key] = answer;
+            value
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&entries).unwrap()
+}
+
+#[test]
+fn test_manifest() {
+    let mut entries = build(&Day::ALL, Day::sample);
+    assert_eq!(entries.len(), Day::ALL.len() * 2);
+    for day in Day::ALL {
+        for part in [1, 2] {
+            assert!(entries.iter().any(|e| e.day == day && e.part == part));
+        }
+    }
+    let day_2 = |e: &&Entry| e.day == Day::D2 && e.part == 1;
+    assert_eq!(
+        entries.iter().find(day_2).unwrap().answer,
+        Ok(Answer::Int(15))
+    );
+
+    let table = to_table(&entries);
+    assert_eq!(table.lines().count(), entries.len() + 1);
+    assert!(table.starts_with("Day  Part  Answer"));
+
+    let json: Value = serde_json::from_str(&to_json(&entries)).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), entries.len());
+    assert_eq!(json[2]["answer"], 15);
+
+    sort(&mut entries, SortBy::Time);
+    assert!(entries.windows(2).all(|w| w[0].elapsed >= w[1].elapsed));
+    sort(&mut entries, SortBy::Day);
+    assert_eq!((entries[0].day, entries[0].part), (Day::D1, 1));
+}
@@ -0,0 +1,119 @@
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "palette";
+
+/// Colors shared by the simulation apps. Each app only reads the fields it
+/// draws, so one palette (and one saved setting) covers all of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Palette {
+    pub visited: Color32,
+    pub knot: Color32,
+    pub arrow: Color32,
+    pub rock: Color32,
+    pub sand: Color32,
+    pub air: Color32,
+}
+
+impl Palette {
+    pub fn dark() -> Self {
+        Self {
+            visited: Color32::DARK_RED,
+            knot: Color32::from_rgb(20, 255, 20),
+            arrow: Color32::YELLOW,
+            rock: Color32::from_rgb(160, 160, 160),
+            sand: Color32::from_rgb(130, 127, 88),
+            air: Color32::from_gray(27),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            visited: Color32::DARK_RED,
+            knot: Color32::from_rgb(20, 140, 20),
+            arrow: Color32::from_rgb(200, 150, 0),
+            rock: Color32::from_gray(90),
+            sand: Color32::from_rgb(194, 178, 128),
+            air: Color32::from_gray(248),
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            visited: Color32::RED,
+            knot: Color32::GREEN,
+            arrow: Color32::YELLOW,
+            rock: Color32::WHITE,
+            sand: Color32::YELLOW,
+            air: Color32::BLACK,
+        }
+    }
+
+    /// The palette saved by a previous run, or the default if there is none.
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|s| eframe::get_value(s, STORAGE_KEY))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, STORAGE_KEY, self);
+    }
+
+    /// Preset buttons followed by a color picker per field.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Dark").clicked() {
+                *self = Self::dark();
+            }
+            if ui.button("Light").clicked() {
+                *self = Self::light();
+            }
+            if ui.button("High contrast").clicked() {
+                *self = Self::high_contrast();
+            }
+        });
+
+        egui::Grid::new("palette_grid").show(ui, |ui| {
+            for (label, color) in [
+                ("Visited", &mut self.visited),
+                ("Knot", &mut self.knot),
+                ("Arrow", &mut self.arrow),
+                ("Rock", &mut self.rock),
+                ("Sand", &mut self.sand),
+                ("Air", &mut self.air),
+            ] {
+                ui.label(label);
+                ui.color_edit_button_srgba(color);
+                ui.end_row();
+            }
+        });
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+#[test]
+fn test_presets() {
+    let dark = Palette::dark();
+    assert_eq!(dark.visited, Color32::DARK_RED);
+    assert_eq!(dark.knot, Color32::from_rgb(20, 255, 20));
+    assert_eq!(dark.arrow, Color32::YELLOW);
+    assert_eq!(dark.rock, Color32::from_rgb(160, 160, 160));
+    assert_eq!(dark.sand, Color32::from_rgb(130, 127, 88));
+    assert_eq!(dark.air, Color32::from_gray(27));
+    assert_eq!(Palette::default(), dark);
+
+    let light = Palette::light();
+    assert_eq!(light.air, Color32::from_gray(248));
+    assert_eq!(light.rock, Color32::from_gray(90));
+
+    let high_contrast = Palette::high_contrast();
+    assert_eq!(high_contrast.air, Color32::BLACK);
+    assert_eq!(high_contrast.rock, Color32::WHITE);
+    assert_eq!(high_contrast.sand, Color32::YELLOW);
+}
@@ -0,0 +1,82 @@
+use std::{fmt, str::FromStr, time::Instant};
+
+use anyhow::{bail, Result};
+
+/// Which parts of a day to solve, picked with `--part 1|2|both`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+    #[default]
+    Both,
+}
+
+impl FromStr for Part {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            "both" => Ok(Part::Both),
+            _ => bail!("unknown part {s:?}, expected 1, 2 or both"),
+        }
+    }
+}
+
+impl Part {
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let Some(i) = args.iter().position(|arg| arg == "--part") else {
+            return Ok(Self::default());
+        };
+        let Some(part) = args.get(i + 1) else {
+            bail!("Usage: --part 1|2|both");
+        };
+        part.parse()
+    }
+}
+
+fn print_answer(part: u8, answer: impl fmt::Display) {
+    let answer = answer.to_string();
+    if answer.contains('\n') {
+        println!("Part {part}:\n{answer}");
+    } else {
+        println!("Part {part}: {answer}");
+    }
+}
+
+/// Solves the selected parts of `input` and prints them the same way for
+/// every day, followed by the combined time on stderr so the answers can
+/// be piped on their own.
+pub fn run_day<A: fmt::Display, B: fmt::Display>(
+    part: Part,
+    solve_1: impl FnOnce(&str) -> Result<A>,
+    solve_2: impl FnOnce(&str) -> Result<B>,
+    input: &str,
+) -> Result<()> {
+    let start = Instant::now();
+    if part != Part::Two {
+        print_answer(1, solve_1(input)?);
+    }
+    if part != Part::One {
+        print_answer(2, solve_2(input)?);
+    }
+    eprintln!("Total time: {:?}", start.elapsed());
+
+    Ok(())
+}
+
+#[test]
+fn test_parse_part() {
+    let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(Part::from_args(&args(&[])).unwrap(), Part::Both);
+    assert_eq!(Part::from_args(&args(&["--part", "1"])).unwrap(), Part::One);
+    assert_eq!(Part::from_args(&args(&["--part", "2"])).unwrap(), Part::Two);
+    assert_eq!(
+        Part::from_args(&args(&["--x", "--part", "both"])).unwrap(),
+        Part::Both
+    );
+    assert!(Part::from_args(&args(&["--part", "3"])).is_err());
+    assert!(Part::from_args(&args(&["--part"])).is_err());
+}
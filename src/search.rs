@@ -0,0 +1,82 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    hash::Hash,
+};
+
+/// Breadth-first search from every node in `starts` at once. Returns the
+/// number of steps to the nearest node where `goal` holds, along with the
+/// path there from the start it was reached from, both ends included.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    neighbors: impl Fn(&N) -> I,
+    goal: impl Fn(&N) -> bool,
+) -> Option<(usize, Vec<N>)>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    // Each node reached so far, with the node it was first reached from.
+    let mut prev = HashMap::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if let Entry::Vacant(e) = prev.entry(start.clone()) {
+            e.insert(None);
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            let mut path =
+                std::iter::successors(Some(node), |n| prev[n].clone()).collect::<Vec<_>>();
+            path.reverse();
+            return Some((path.len() - 1, path));
+        }
+
+        for next in neighbors(&node) {
+            if let Entry::Vacant(e) = prev.entry(next.clone()) {
+                e.insert(Some(node.clone()));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+fn edges(node: &char) -> Vec<char> {
+    // a - b - c - d - e, with a shortcut a - f - e and a dead end g.
+    match node {
+        'a' => vec!['b', 'f'],
+        'b' => vec!['a', 'c'],
+        'c' => vec!['b', 'd'],
+        'd' => vec!['c', 'e'],
+        'e' => vec!['d', 'f'],
+        'f' => vec!['a', 'e', 'g'],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn test_bfs() {
+    assert_eq!(
+        bfs(['a'], edges, |&n| n == 'e'),
+        Some((2, vec!['a', 'f', 'e']))
+    );
+    assert_eq!(
+        bfs(['c'], edges, |&n| n == 'a'),
+        Some((2, vec!['c', 'b', 'a']))
+    );
+    assert_eq!(bfs(['a'], edges, |&n| n == 'a'), Some((0, vec!['a'])));
+    assert_eq!(bfs(['g'], edges, |&n| n == 'a'), None);
+    assert_eq!(bfs(['a'], edges, |&n| n == 'z'), None);
+}
+
+#[test]
+fn test_bfs_many_starts() {
+    // `d` is one step from `c` and `e`, two from `a`.
+    let (steps, path) = bfs(['a', 'c'], edges, |&n| n == 'd').unwrap();
+    assert_eq!((steps, path), (1, vec!['c', 'd']));
+    assert_eq!(bfs(Vec::<char>::new(), edges, |_| true), None);
+}
@@ -0,0 +1,16 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Writes a simulation's state to `path` as JSON.
+pub fn save<T: Serialize>(state: &T, path: &Path) -> Result<()> {
+    let json = serde_json::to_string(state)?;
+    fs::write(path, json).with_context(|| format!("writing {}", path.display()))
+}
+
+/// Reads back a state written by `save`.
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let json = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    Ok(serde_json::from_str(&json)?)
+}
@@ -0,0 +1,228 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use egui::{Align2, Key};
+
+/// A simulation that advances in discrete steps until it is done, so the
+/// egui days can share their controls.
+pub trait Steppable {
+    fn step(&mut self);
+    fn is_done(&self) -> bool;
+    /// Starts over from the original input.
+    fn reset(&mut self);
+}
+
+/// Steps until the simulation is done and returns how many steps it took.
+pub fn run_to_completion(sim: &mut dyn Steppable) -> usize {
+    let mut steps = 0;
+    while !sim.is_done() {
+        sim.step();
+        steps += 1;
+    }
+    steps
+}
+
+/// Steps up to `times` times, stopping early once the simulation is done.
+/// Returns how many steps actually ran.
+pub fn advance(sim: &mut dyn Steppable, times: u32) -> usize {
+    let mut steps = 0;
+    for _ in 0..times {
+        if sim.is_done() {
+            break;
+        }
+        sim.step();
+        steps += 1;
+    }
+    steps
+}
+
+/// How long a headless run to completion took.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchReport {
+    pub steps: usize,
+    pub elapsed: Duration,
+}
+
+impl BenchReport {
+    pub fn steps_per_second(&self) -> f64 {
+        self.steps as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} steps in {:.2?} ({:.0} steps/s)",
+            self.steps,
+            self.elapsed,
+            self.steps_per_second()
+        )
+    }
+}
+
+/// Runs the simulation to completion without drawing anything, timing it.
+pub fn bench(sim: &mut dyn Steppable) -> BenchReport {
+    let start = Instant::now();
+    let steps = run_to_completion(sim);
+    BenchReport {
+        steps,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Frame and step rates averaged over about half a second, so the overlay
+/// doesn't flicker from frame to frame.
+#[derive(Debug, Default, Clone)]
+pub struct RateMeter {
+    elapsed: f32,
+    frames: usize,
+    steps: usize,
+    fps: f32,
+    steps_per_second: f32,
+}
+
+impl RateMeter {
+    const WINDOW: f32 = 0.5;
+
+    /// Counts one frame that took `dt` seconds and ran `steps` steps.
+    pub fn record(&mut self, dt: f32, steps: usize) {
+        self.elapsed += dt;
+        self.frames += 1;
+        self.steps += steps;
+        if self.elapsed >= Self::WINDOW {
+            *self = Self {
+                fps: self.frames as f32 / self.elapsed,
+                steps_per_second: self.steps as f32 / self.elapsed,
+                ..Self::default()
+            };
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn steps_per_second(&self) -> f32 {
+        self.steps_per_second
+    }
+
+    /// The mean frame time in milliseconds, zero before the first window.
+    pub fn frame_ms(&self) -> f32 {
+        if self.fps > 0.0 {
+            1000.0 / self.fps
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The last frame's time, along with `steps` run in it, recorded into
+/// `meter`, and if `show` is set, its rates drawn in the bottom right
+/// corner of the window.
+pub fn rate_overlay(ctx: &egui::Context, meter: &mut RateMeter, steps: usize, show: bool) {
+    meter.record(ctx.input(|i| i.unstable_dt), steps);
+    if !show {
+        return;
+    }
+    egui::Area::new("rate_overlay")
+        .anchor(Align2::RIGHT_BOTTOM, [-8.0, -8.0])
+        .interactable(false)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{:.0} fps ({:.1} ms), {:.0} steps/s",
+                meter.fps(),
+                meter.frame_ms(),
+                meter.steps_per_second()
+            ));
+        });
+}
+
+/// The Reset, Step and Finish buttons.
+pub fn controls(ui: &mut egui::Ui, sim: &mut dyn Steppable) {
+    if ui.button("Reset").clicked() {
+        sim.reset();
+    }
+    if ui.button("Step").clicked() {
+        sim.step();
+    }
+    if ui.button("Finish").clicked() {
+        run_to_completion(sim);
+    }
+}
+
+/// The same actions from the keyboard: `→` steps, `R` resets and `End`
+/// runs to completion. Ignored while a text field has focus.
+pub fn handle_keys(ctx: &egui::Context, sim: &mut dyn Steppable) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+
+    let (step, reset, finish) = ctx.input(|i| {
+        (
+            i.key_pressed(Key::ArrowRight),
+            i.key_pressed(Key::R),
+            i.key_pressed(Key::End),
+        )
+    });
+
+    if reset {
+        sim.reset();
+    }
+    if step {
+        sim.step();
+    }
+    if finish {
+        run_to_completion(sim);
+    }
+}
+
+#[cfg(test)]
+struct Countdown(usize);
+
+#[cfg(test)]
+impl Steppable for Countdown {
+    fn step(&mut self) {
+        self.0 = self.0.saturating_sub(1);
+    }
+
+    fn is_done(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn reset(&mut self) {}
+}
+
+#[test]
+fn test_advance() {
+    let mut sim = Countdown(7);
+    assert_eq!(advance(&mut sim, 5), 5);
+    assert_eq!(advance(&mut sim, 5), 2);
+    assert_eq!(advance(&mut sim, 5), 0);
+}
+
+#[test]
+fn test_rate_meter() {
+    let mut meter = RateMeter::default();
+    assert_eq!((meter.fps(), meter.steps_per_second()), (0.0, 0.0));
+    assert_eq!(meter.frame_ms(), 0.0);
+
+    // 1/32s frames running 4 steps each, one short of a full window.
+    for _ in 0..15 {
+        meter.record(0.03125, 4);
+    }
+    assert_eq!(meter.fps(), 0.0);
+    meter.record(0.03125, 4);
+    assert_eq!(meter.fps(), 32.0);
+    assert_eq!(meter.steps_per_second(), 128.0);
+    assert_eq!(meter.frame_ms(), 31.25);
+
+    // The next window starts over: paused, with slower frames.
+    for _ in 0..4 {
+        meter.record(0.125, 0);
+    }
+    assert_eq!(meter.fps(), 8.0);
+    assert_eq!(meter.steps_per_second(), 0.0);
+}
@@ -0,0 +1,102 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::RangeInclusive};
+
+use itertools::Itertools;
+
+/// Keeps the `k` largest items pushed into it. The smallest kept item sits
+/// on top of a min-heap, so each push is O(log k).
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.heap.push(Reverse(item));
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    /// The kept items, largest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|rev| rev.0)
+            .collect()
+    }
+}
+
+/// The `k` largest items, largest first.
+pub fn top_k<T: Ord + Copy>(items: impl Iterator<Item = T>, k: usize) -> Vec<T> {
+    let mut top = TopK::new(k);
+    items.for_each(|item| top.push(item));
+    top.into_sorted_vec()
+}
+
+/// The greatest common divisor, with `gcd(a, 0) == a`.
+pub fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The fewest ranges covering exactly the cells `ranges` cover, sorted by
+/// start. Ranges that overlap or touch (`b.start - 1 <= a.end`) are merged,
+/// since on integers `1..=2` and `3..=4` leave no gap between them.
+pub fn merge_ranges(ranges: impl Iterator<Item = RangeInclusive<i64>>) -> Vec<RangeInclusive<i64>> {
+    ranges
+        .sorted_by_key(|r| *r.start())
+        .coalesce(|a, b| {
+            if b.start() - 1 <= *a.end() {
+                Ok(*a.start()..=*a.end().max(b.end()))
+            } else {
+                Err((a, b))
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_merge_ranges() {
+    let merge = |ranges: &[RangeInclusive<i64>]| merge_ranges(ranges.iter().cloned());
+
+    // Fully nested.
+    assert_eq!(merge(&[0..=10, 2..=5, 3..=3]), vec![0..=10]);
+    // Partially overlapping, in any order.
+    assert_eq!(merge(&[4..=9, 0..=5]), vec![0..=9]);
+    // Exactly adjacent.
+    assert_eq!(merge(&[-3..=-1, 0..=2, 3..=3]), vec![-3..=3]);
+    // Disjoint, with a gap of one.
+    assert_eq!(merge(&[5..=6, 0..=3]), vec![0..=3, 5..=6]);
+    assert_eq!(merge(&[]), vec![]);
+}
+
+#[test]
+fn test_gcd() {
+    assert_eq!(gcd(12, 18), 6);
+    assert_eq!(gcd(18, 12), 6);
+    assert_eq!(gcd(7, 13), 1);
+    assert_eq!(gcd(5, 0), 5);
+    assert_eq!(gcd(0, 0), 0);
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_top_k(items in proptest::collection::vec(0..50u32, 0..100), k in 0..10usize) {
+        let mut naive = items.clone();
+        naive.sort_by_key(|&i| Reverse(i));
+        naive.truncate(k);
+
+        proptest::prop_assert_eq!(top_k(items.into_iter(), k), naive);
+    }
+}
@@ -0,0 +1,56 @@
+use egui::{Pos2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::coord::Coord;
+
+/// Where grid cells land on screen: cell `c` is centered on
+/// `origin + c * cell * zoom`. `cell` is per axis so a grid stretched to
+/// fill a panel can use it too.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewTransform {
+    pub origin: Vec2,
+    pub zoom: f32,
+    pub cell: Vec2,
+}
+
+impl ViewTransform {
+    /// The size of one cell on screen.
+    pub fn side(&self) -> Vec2 {
+        self.cell * self.zoom
+    }
+
+    pub fn world_to_screen(&self, c: Coord) -> Pos2 {
+        (self.origin + Vec2::new(c.x as f32, c.y as f32) * self.side()).to_pos2()
+    }
+
+    /// The cell drawn under `pos`, including positions off the grid.
+    pub fn screen_to_world(&self, pos: Pos2) -> Coord {
+        let cell = ((pos.to_vec2() - self.origin) / self.side() + Vec2::splat(0.5)).floor();
+        Coord {
+            x: cell.x as i32,
+            y: cell.y as i32,
+        }
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    for zoom in [0.1, 0.5, 1.0, 2.5, 10.0] {
+        for cell in [Vec2::splat(1.0), Vec2::splat(15.0), Vec2::new(2.0, 3.0)] {
+            let view = ViewTransform {
+                origin: Vec2::new(-37.5, 120.0),
+                zoom,
+                cell,
+            };
+            for x in -20..20 {
+                for y in -20..20 {
+                    let c = Coord { x, y };
+                    assert_eq!(view.screen_to_world(view.world_to_screen(c)), c);
+
+                    let inside = view.world_to_screen(c) + view.side() * 0.49;
+                    assert_eq!(view.screen_to_world(inside), c);
+                }
+            }
+        }
+    }
+}
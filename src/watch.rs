@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use notify::{RecursiveMode, Watcher};
+
+use crate::day::Day;
+
+/// How long to wait for a file event before checking the debouncer again.
+const TICK: Duration = Duration::from_millis(100);
+/// How long the file has to stay unchanged before it is solved again, so an
+/// editor writing it in several steps only triggers one solve.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Turns a burst of file events into a single change.
+pub struct Debouncer {
+    last_event: Option<Instant>,
+    delay: Duration,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            last_event: None,
+            delay,
+        }
+    }
+
+    /// Records an event for the file at `now`.
+    pub fn note(&mut self, now: Instant) {
+        self.last_event = Some(now);
+    }
+
+    /// True once events have come in and then stopped for the delay, once
+    /// per burst.
+    pub fn ready(&mut self, now: Instant) -> bool {
+        match self.last_event {
+            Some(at) if now.duration_since(at) >= self.delay => {
+                self.last_event = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Clears the terminal and prints each part of `day` for `input`. A part
+/// that fails prints its error instead, so watching carries on.
+pub fn on_change(day: Day, parts: &[u8], input: &str, out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[2J\x1b[H")?;
+    for &part in parts {
+        match day.run(part, input) {
+            Ok(answer) => {
+                let answer = answer.to_string();
+                let sep = if answer.contains('\n') { "\n" } else { " " };
+                writeln!(out, "Day {} part {part}:{sep}{answer}", day.number())?;
+            }
+            Err(e) => writeln!(out, "Day {} part {part}: error: {e}", day.number())?,
+        }
+    }
+    out.flush()
+}
+
+/// Solves `day` for the file at `path`, then again every time it changes,
+/// until the process is stopped.
+pub fn watch(path: PathBuf, day: Day, parts: &[u8]) -> Result<()> {
+    let read = || fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()));
+    let mut stdout = io::stdout().lock();
+    on_change(day, parts, &read()?, &mut stdout)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // Editors often save by renaming a new file over the old one, which a
+    // watch on the file itself stops seeing, so this watches its directory.
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    let mut debouncer = Debouncer::new(DEBOUNCE);
+    loop {
+        match rx.recv_timeout(TICK) {
+            Ok(event) => {
+                if event?
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == path.file_name())
+                {
+                    debouncer.note(Instant::now());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => bail!("the file watcher stopped"),
+        }
+        if debouncer.ready(Instant::now()) {
+            match read() {
+                Ok(input) => on_change(day, parts, &input, &mut stdout)?,
+                Err(e) => writeln!(stdout, "{e:#}")?,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_debouncer() {
+    let start = Instant::now();
+    let at = |ms| start + Duration::from_millis(ms);
+
+    let mut debouncer = Debouncer::new(Duration::from_millis(300));
+    assert!(!debouncer.ready(at(1000)));
+
+    debouncer.note(at(100));
+    assert!(!debouncer.ready(at(200)));
+    // Another event before the delay is up starts it over.
+    debouncer.note(at(300));
+    assert!(!debouncer.ready(at(500)));
+    assert!(debouncer.ready(at(600)));
+    assert!(!debouncer.ready(at(1000)));
+
+    debouncer.note(at(1100));
+    assert!(debouncer.ready(at(1400)));
+}
+
+#[test]
+fn test_on_change() {
+    let mut out = vec![];
+    on_change(Day::D6, &[1, 2], "mjqjpqmgbljsphdztnvjfqwrcgsmlb", &mut out).unwrap();
+    let first = String::from_utf8(out).unwrap();
+    assert!(
+        first.ends_with("Day 6 part 1: 7\nDay 6 part 2: 19\n"),
+        "{first:?}"
+    );
+
+    let mut out = vec![];
+    on_change(Day::D6, &[1], "bvwbjplbgvbhsrlpgdmjqwftvncz", &mut out).unwrap();
+    let second = String::from_utf8(out).unwrap();
+    assert!(second.starts_with("\x1b[2J"));
+    assert!(second.ends_with("Day 6 part 1: 5\n"), "{second:?}");
+
+    let mut out = vec![];
+    on_change(Day::D6, &[1], "aaaa", &mut out).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .ends_with("Day 6 part 1: error: no marker found\n"));
+}
@@ -0,0 +1,38 @@
+use advent_of_code::{
+    day_12::{shortest_steps, SearchMode},
+    day_14::settled_count,
+    day_9::tail_visits,
+};
+
+#[test]
+fn test_tail_visits() {
+    let input = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2";
+    assert_eq!(tail_visits(input, 2).unwrap(), 13);
+    assert_eq!(tail_visits(input, 10).unwrap(), 1);
+
+    let larger = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20";
+    assert_eq!(tail_visits(larger, 10).unwrap(), 36);
+    assert!(tail_visits(input, 11).is_err());
+
+    assert_eq!(tail_visits("R 0\nR 2\nU 0", 2).unwrap(), 2);
+    let err = tail_visits("R 4\n\nU x", 2).unwrap_err();
+    assert_eq!(err.to_string(), "line 3: invalid move \"U x\"");
+}
+
+#[test]
+fn test_shortest_steps() {
+    let input = include_str!("../src/test_files/day_12_test.txt");
+    assert_eq!(shortest_steps(input, SearchMode::Start).unwrap(), Some(31));
+    assert_eq!(
+        shortest_steps(input, SearchMode::AnyLowest).unwrap(),
+        Some(29)
+    );
+    assert_eq!(shortest_steps("SzE", SearchMode::Start).unwrap(), None);
+}
+
+#[test]
+fn test_settled_count() {
+    let input = include_str!("../src/test_files/day_14_test.txt");
+    assert_eq!(settled_count(input, false).unwrap(), 24);
+    assert_eq!(settled_count(input, true).unwrap(), 93);
+}
@@ -0,0 +1,57 @@
+use std::{fs, process::Command};
+
+#[test]
+fn test_input_dir() {
+    let dir = std::env::temp_dir().join(format!("aoc_input_dir_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.txt"), "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n").unwrap();
+    fs::write(dir.join("b.txt"), "bvwbjplbgvbhsrlpgdmjqwftvncz\n").unwrap();
+    fs::write(dir.join("c.txt"), "aaaaaaaa\n").unwrap();
+    fs::write(dir.join("notes.md"), "not an input\n").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .args(["6", "--part", "1", "--input-dir"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout, "a.txt: 7\nb.txt: 5\n");
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("c.txt: no marker found"), "{stderr}");
+    assert!(!stderr.contains("notes.md"));
+    assert!(!out.status.success());
+}
+
+#[test]
+fn test_input_dir_malformed_file() {
+    let dir = std::env::temp_dir().join(format!("aoc_input_dir_bad_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("bad.txt"), "noop\nbogus 3\n").unwrap();
+    fs::write(
+        dir.join("good.txt"),
+        include_str!("../src/test_files/day_10_test.txt"),
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_aoc"))
+        .args(["10", "--part", "1", "--input-dir"])
+        .arg(&dir)
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout, "good.txt: 13140\n");
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(
+        stderr.contains("bad.txt: line 2: invalid instruction \"bogus 3\""),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("1 answers failed across 2 inputs"),
+        "{stderr}"
+    );
+    assert!(!out.status.success());
+}
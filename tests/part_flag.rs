@@ -0,0 +1,33 @@
+use std::process::{Command, Output};
+
+fn day_6(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_day_6"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_part_flag() {
+    let out = day_6(&["--part", "1"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with("Part 1: "));
+    assert!(String::from_utf8(out.stderr)
+        .unwrap()
+        .starts_with("Total time: "));
+
+    let stdout = String::from_utf8(day_6(&["--part", "2"]).stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with("Part 2: "));
+
+    let stdout = String::from_utf8(day_6(&[]).stdout).unwrap();
+    assert_eq!(
+        stdout,
+        String::from_utf8(day_6(&["--part", "both"]).stdout).unwrap()
+    );
+    assert_eq!(stdout.lines().count(), 2);
+
+    assert!(!day_6(&["--part", "3"]).status.success());
+}
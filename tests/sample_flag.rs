@@ -0,0 +1,21 @@
+use std::process::Command;
+
+fn day_11(args: &[&str]) -> String {
+    let out = Command::new(env!("CARGO_BIN_EXE_day_11"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+#[test]
+fn test_sample_flag() {
+    let sample = day_11(&["--sample"]);
+    assert!(sample.contains("Part 1: 10605\n"), "{sample}");
+    assert!(sample.contains("Part 2: 2713310158\n"), "{sample}");
+
+    let real = day_11(&[]);
+    assert!(real.contains("Part 1: 111210\n"), "{real}");
+    assert!(real.contains("Part 2: 15447387620\n"), "{real}");
+}